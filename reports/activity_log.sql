@@ -0,0 +1,17 @@
+.mode column
+.headers yes
+
+-- Every time log with its summary, e.g. for activity reports to customers
+select
+    substr(t.date, 1, 10) as "Date",
+    u.username as "User",
+    p.full_path || coalesce('#' || i.iid, '!' || mr.iid) as "Reference",
+    coalesce(i.name, mr.name) as "Title",
+    round(t.hours, 2) as "Time (h)",
+    coalesce(t.summary, '') as "Summary"
+from TimeLog t
+inner join User u on t.user_id = u.id
+left join Issue i on t.issue_id = i.id
+left join MergeRequest mr on t.merge_request_id = mr.id
+inner join Project p on p.id = coalesce(i.project_id, mr.project_id)
+order by t.date, u.username;
@@ -0,0 +1,16 @@
+.mode column
+.headers yes
+
+-- Members of the crawled groups and projects who logged no time in the fetched range
+select
+    u.username as "User",
+    u.name as "Name",
+    group_concat(distinct coalesce(g.full_path, p.full_path)) as "Member of"
+from Membership m
+inner join User u on m.user_id = u.id
+left join "Group" g on m.group_id = g.id
+left join Project p on m.project_id = p.id
+where not u.bot
+    and not exists (select 1 from TimeLog t where t.user_id = u.id)
+group by u.id
+order by u.username;
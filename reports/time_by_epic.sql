@@ -0,0 +1,14 @@
+.mode column
+.headers yes
+
+-- Time spent per epic, on its own issues and including all of its child epics
+select
+    g.full_path || '&' || e.iid as "Epic",
+    e.name as "Title",
+    e.state as "State",
+    round(et.hours, 2) as "Own time (h)",
+    round(et.total_hours, 2) as "Time incl. child epics (h)"
+from Epic e
+inner join EpicTime et on et.epic_id = e.id
+inner join "Group" g on e.group_id = g.id
+order by g.full_path, e.iid;
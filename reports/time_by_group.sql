@@ -0,0 +1,29 @@
+.mode column
+.headers yes
+
+-- Time spent per group, including the time spent in all of its subgroups
+with recursive group_tree(root_id, group_id) as (
+    select id, id from "Group"
+    union all
+    select gt.root_id, g.id
+    from group_tree gt
+    inner join "Group" g on g.parent_id = gt.group_id
+),
+project_time(project_id, hours) as (
+    select i.project_id, t.hours
+    from TimeLog t
+    inner join Issue i on t.issue_id = i.id
+    union all
+    select mr.project_id, t.hours
+    from TimeLog t
+    inner join MergeRequest mr on t.merge_request_id = mr.id
+)
+select
+    g.full_path as "Group",
+    round(total(pt.hours), 2) as "Time (h)"
+from "Group" g
+inner join group_tree gt on gt.root_id = g.id
+left join Project p on p.group_id = gt.group_id
+left join project_time pt on pt.project_id = p.id
+group by g.id
+order by g.full_path;
@@ -0,0 +1,15 @@
+.mode column
+.headers yes
+
+-- Time spent per issue, including its share of the time spent on the merge requests closing it
+select
+    p.full_path || '#' || i.iid as "Issue",
+    i.name as "Title",
+    round(it.hours, 2) as "Issue time (h)",
+    round(it.merge_request_hours, 2) as "Merge request time (h)",
+    round(it.total_hours, 2) as "Total time (h)"
+from Issue i
+inner join IssueTime it on it.issue_id = i.id
+inner join Project p on i.project_id = p.id
+where it.total_seconds != 0
+order by "Total time (h)" desc;
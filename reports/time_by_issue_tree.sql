@@ -0,0 +1,15 @@
+.mode column
+.headers yes
+
+-- Time spent per top-level issue, on the issue itself and including all of its tasks and other
+-- child items
+select
+    p.full_path || '#' || i.iid as "Issue",
+    i.name as "Title",
+    round(itt.hours, 2) as "Own time (h)",
+    round(itt.total_hours, 2) as "Time incl. children (h)"
+from Issue i
+inner join IssueTreeTime itt on itt.issue_id = i.id
+inner join Project p on i.project_id = p.id
+where i.parent_id is null and itt.total_seconds != 0
+order by "Time incl. children (h)" desc;
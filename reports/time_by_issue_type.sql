@@ -0,0 +1,13 @@
+.mode column
+.headers yes
+
+-- Time spent per issue type, like incidents versus feature work. Every time log is counted once,
+-- so time spent on tasks is not counted again for their parent issue.
+select
+    i.type as "Type",
+    count(distinct i.id) as "Issues",
+    round(total(t.hours), 2) as "Time (h)"
+from Issue i
+inner join TimeLog t on t.issue_id = i.id
+group by i.type
+order by "Time (h)" desc;
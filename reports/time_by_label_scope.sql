@@ -0,0 +1,21 @@
+.mode column
+.headers yes
+
+-- Time spent per scoped label (like `type::bug`) on issues and merge requests carrying the label
+select
+    l.scope as "Scope",
+    l.value as "Value",
+    round(total(t.hours), 2) as "Time (h)"
+from Label l
+inner join (
+    select il.label_id, t.hours
+    from IssueLabel il
+    inner join TimeLog t on t.issue_id = il.issue_id
+    union all
+    select ml.label_id, t.hours
+    from MergeRequestLabel ml
+    inner join TimeLog t on t.merge_request_id = ml.merge_request_id
+) t on t.label_id = l.id
+where l.scope is not null
+group by l.scope, l.value
+order by l.scope, "Time (h)" desc;
@@ -0,0 +1,37 @@
+.mode column
+.headers yes
+
+-- Time spent on each milestone's issues and merge requests, split into time logged within the
+-- milestone's start and due date (if set) and time logged outside of it
+with milestone_time(milestone_id, date, hours) as (
+    select i.milestone_id, t.date, t.hours
+    from TimeLog t
+    inner join Issue i on t.issue_id = i.id
+    union all
+    select mr.milestone_id, t.date, t.hours
+    from TimeLog t
+    inner join MergeRequest mr on t.merge_request_id = mr.id
+)
+select
+    ms.name as "Milestone",
+    coalesce(g.full_path, p.full_path) as "Owner",
+    ms.state as "State",
+    substr(ms.start_date, 1, 10) as "Start",
+    substr(ms.due_date, 1, 10) as "Due",
+    round(total(case
+        when substr(mt.date, 1, 10) between coalesce(substr(ms.start_date, 1, 10), '0000-00-00')
+            and coalesce(substr(ms.due_date, 1, 10), '9999-99-99')
+        then mt.hours
+    end), 2) as "Within period (h)",
+    round(total(case
+        when substr(mt.date, 1, 10) between coalesce(substr(ms.start_date, 1, 10), '0000-00-00')
+            and coalesce(substr(ms.due_date, 1, 10), '9999-99-99')
+        then 0
+        else mt.hours
+    end), 2) as "Outside period (h)"
+from Milestone ms
+left join "Group" g on ms.group_id = g.id
+left join Project p on ms.project_id = p.id
+left join milestone_time mt on mt.milestone_id = ms.id
+group by ms.id
+order by ms.start_date, ms.name;
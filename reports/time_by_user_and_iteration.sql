@@ -0,0 +1,18 @@
+.mode column
+.headers yes
+
+-- Time spent per iteration (sprint) and user on the iteration's issues
+select
+    c.name as "Cadence",
+    it.name as "Iteration",
+    substr(it.start_date, 1, 10) as "Start",
+    substr(it.due_date, 1, 10) as "Due",
+    u.username as "User",
+    round(total(t.hours), 2) as "Time (h)"
+from Iteration it
+inner join IterationCadence c on it.cadence_id = c.id
+inner join Issue i on i.iteration_id = it.id
+inner join TimeLog t on t.issue_id = i.id
+inner join User u on t.user_id = u.id
+group by it.id, u.id
+order by c.name, it.start_date, u.username;
@@ -11,25 +11,25 @@ from (
     select distinct
         u.username as user,
         ms.name as milestone,
-        round(total(t.time) / 60.0, 2) as time_issue,
+        round(total(t.hours), 2) as time_issue,
         0 as time_mr
     from Milestone ms
     cross join User u
     left join Issue i on i.milestone_id = ms.id
     left join TimeLog t on t.issue_id= i.id and t.user_id = u.id
-    where t.time is not null
+    where t.seconds is not null
     group by Milestone, User
     union
 
     select distinct
         u.username as user,
         "No milestone" as milestone,
-        round(total(t.time) / 60.0, 2) as time_issue,
+        round(total(t.hours), 2) as time_issue,
         0 as time_mr
     from User u
     left join Issue i on i.milestone_id is null
     left join TimeLog t on t.issue_id= i.id and t.user_id = u.id
-    where t.time is not null
+    where t.seconds is not null
     group by Milestone, User
 
     union
@@ -38,12 +38,12 @@ from (
         u.username as user,
         ms.name as milestone,
         0 as time_issue,
-        round(total(t.time) / 60.0, 2) as time_mr
+        round(total(t.hours), 2) as time_mr
     from Milestone ms
     cross join User u
     left join MergeRequest m on m.milestone_id = ms.id
     left join TimeLog t on t.merge_request_id= m.id and t.user_id = u.id
-    where t.time is not null
+    where t.seconds is not null
     group by Milestone, User
 
     union
@@ -52,11 +52,11 @@ from (
         u.username as user,
         "No milestone" as milestone,
         0 as time_issue,
-        round(total(t.time) / 60.0, 2) as time_mr
+        round(total(t.hours), 2) as time_mr
     from User u
     left join MergeRequest m on m.milestone_id is null
     left join TimeLog t on t.merge_request_id= m.id and t.user_id = u.id
-    where t.time is not null
+    where t.seconds is not null
     group by Milestone, User
 )
 order by "Time (h)" desc;
\ No newline at end of file
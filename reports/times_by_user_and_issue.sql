@@ -4,7 +4,7 @@
 select
     u.username as "User",
     ms.name as "Milestone",
-    round(t.time / 60.0, 2) "Time (h)",
+    round(t.hours, 2) "Time (h)",
     i.iid as "Issue",
     mr.iid as "Mergerequest",
     t.date as "Date"
@@ -14,11 +14,11 @@ inner join User u on t.user_id = u.id
 left join Issue i on t.issue_id = i.id and i.milestone_id = ms.id
 left join MergeRequest mr on t.merge_request_id = mr.id and mr.milestone_id = ms.id
 where i.iid is not null or mr.iid is not null
-union
+union all
 select
     u.username as "User",
     "No milestone" as "Milestone",
-    round(t.time / 60.0, 2) "Time (h)",
+    round(t.hours, 2) "Time (h)",
     i.iid as "Issue",
     mr.iid as "Mergerequest",
     t.date as "Date"
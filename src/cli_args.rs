@@ -1,28 +1,222 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub(crate) struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(
         short,
         long,
+        required = true,
         help = "GraphQL API URI. Usually something like 'https://gitlab.com/api/graphql'."
     )]
-    pub uri: String,
+    pub uri: Option<String>,
 
     #[arg(
         short,
         long,
+        required = true,
         help = "'Personal Access Token' used for fetching. See https://docs.gitlab.com/ee/user/profile/personal_access_tokens.html."
     )]
-    pub token: String,
+    pub token: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "The name of the GitLab group to fetch"
+    )]
+    pub group: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        help = "Also fetch projects and milestones of all subgroups of the group"
+    )]
+    pub include_subgroups: bool,
+
+    #[arg(
+        long,
+        value_parser = parse_date,
+        help = "Only fetch time logs spent on or after this day (YYYY-MM-DD)"
+    )]
+    pub since: Option<String>,
+
+    #[arg(
+        long,
+        value_parser = parse_date,
+        help = "Only fetch time logs spent on or before this day (YYYY-MM-DD)"
+    )]
+    pub until: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Strategy::Projects,
+        help = "How time logs are crawled"
+    )]
+    pub strategy: Strategy,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Only fetch issues of these types (comma separated), along with their time logs. Defaults to all types."
+    )]
+    pub issue_types: Vec<IssueType>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also fetch the issues each merge request closes or mentions. Needs two additional requests per merge request."
+    )]
+    pub merge_request_issues: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also fetch the links between issues. Needs an additional request per issue."
+    )]
+    pub issue_links: bool,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 4,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Maximum number of projects fetched concurrently"
+    )]
+    pub jobs: usize,
+
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Maximum number of retries of a failed API request"
+    )]
+    pub max_retries: u32,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_name = "SECONDS",
+        help = "Delay before the first retry of a failed API request, doubled for every further retry"
+    )]
+    pub retry_delay: u64,
 
-    #[arg(short, long, help = "The name of the GitLab group to fetch")]
-    pub group: String,
+    #[arg(
+        long,
+        default_value_t = 60,
+        value_name = "SECONDS",
+        help = "Maximum delay between two retries, unless GitLab asks to wait longer"
+    )]
+    pub max_retry_delay: u64,
 
     #[arg(short, long, default_value_t = false, help = "Overwrite out file")]
     pub force: bool,
 
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        help = "Update an existing out file with everything changed since its last successful run"
+    )]
+    pub sync: bool,
+
     #[arg()]
     pub out_file: Option<String>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Strategy {
+    /// Crawl all issues and merge requests of every project
+    Projects,
+    /// Crawl the group's time logs and derive issues and merge requests from them.
+    /// Needs far fewer requests, but always fetches all time logs, even with --sync.
+    GroupTimelogs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum IssueType {
+    Issue,
+    Incident,
+    TestCase,
+    Requirement,
+    Task,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Manage existing database files
+    #[command(subcommand)]
+    Db(DbCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum DbCommand {
+    /// Upgrade a database file to the current schema version
+    Migrate {
+        #[arg(help = "The database file to upgrade")]
+        file: String,
+    },
+}
+
+/// Accepts existing days formatted as `YYYY-MM-DD`
+fn parse_date(value: &str) -> Result<String, String> {
+    let is_formatted = value.len() == 10
+        && value.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+    if !is_formatted {
+        return Err(format!("'{}' is not formatted as YYYY-MM-DD", value));
+    }
+
+    let number = |range: std::ops::Range<usize>| value[range].parse::<u32>().unwrap_or_default();
+    let (year, month, day) = (number(0..4), number(5..7), number(8..10));
+    let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year => 29,
+        2 => 28,
+        _ => return Err(format!("'{}' has no month {}", value, month)),
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return Err(format!("'{}' has no day {}", value, day));
+    }
+
+    Ok(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_date_accepts_existing_days() {
+        assert_eq!(parse_date("2024-01-31"), Ok("2024-01-31".to_string()));
+        assert_eq!(parse_date("2024-02-29"), Ok("2024-02-29".to_string()));
+        assert_eq!(parse_date("2000-02-29"), Ok("2000-02-29".to_string()));
+    }
+
+    #[test]
+    fn parse_date_rejects_impossible_days() {
+        assert!(parse_date("2024-13-45").is_err());
+        assert!(parse_date("2024-00-10").is_err());
+        assert!(parse_date("2024-04-31").is_err());
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("1900-02-29").is_err());
+        assert!(parse_date("2024-01-00").is_err());
+        assert!(parse_date("2024-1-01").is_err());
+    }
+}
@@ -1,104 +1,279 @@
-use crate::model::*;
+use crate::{model::*, DateRange, DbError, FetchResult};
 use anyhow::Context;
+use migrations::MIGRATIONS;
 use rusqlite as sql;
+use sql::OptionalExtension;
 
-pub(crate) fn save_to_db(
-    file_path: impl AsRef<str>,
-    projects: Vec<Project>,
-    milestones: Vec<Milestone>,
-    issues: Vec<Issue>,
-    merge_requests: Vec<MergeRequest>,
-    time_logs: Vec<TimeLog>,
-    users: Vec<User>,
-) -> anyhow::Result<()> {
-    let conn = sql::Connection::open(file_path.as_ref())?;
-    create_tables(&conn)?;
-    insert_users(&conn, &users)?;
-    insert_projects(&conn, &projects)?;
-    insert_milestones(&conn, &milestones)?;
-    insert_issues(&conn, &issues)?;
-    insert_merge_requests(&conn, &merge_requests)?;
-    insert_time_logs(&conn, &time_logs)?;
-    conn.close().map_err(|(_, e)| e)?;
+mod migrations;
 
-    Ok(())
+/// Schema version of databases written by this version
+pub(crate) const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Opens (or creates) the database at `file_path` and migrates it to the current schema version
+pub(crate) fn open(file_path: impl AsRef<str>) -> anyhow::Result<sql::Connection> {
+    let mut conn = sql::Connection::open(file_path.as_ref())?;
+    migrate(&mut conn)?;
+    Ok(conn)
 }
 
-fn create_tables(conn: &sql::Connection) -> anyhow::Result<()> {
-    conn.execute(
-        "CREATE TABLE User (id INTEGER NOT NULL PRIMARY KEY, username VARCHAR)",
-        [],
-    )?;
+/// Returns the start time and the time log range of the last finished run for `group_path`, if any
+pub(crate) fn last_successful_run(
+    conn: &sql::Connection,
+    group_path: &str,
+) -> anyhow::Result<Option<(String, DateRange)>> {
+    let run = conn
+        .query_row(
+            "SELECT started_at, since, until FROM FetchRun
+            WHERE group_path = ? AND finished_at IS NOT NULL
+            ORDER BY started_at DESC, id DESC
+            LIMIT 1",
+            [group_path],
+            |row| {
+                let range = DateRange {
+                    since: row.get(1)?,
+                    until: row.get(2)?,
+                };
+                Ok((row.get(0)?, range))
+            },
+        )
+        .optional()?;
+    Ok(run)
+}
 
-    conn.execute(
-        "CREATE TABLE Project (id INTEGER NOT NULL PRIMARY KEY, name VARCHAR)",
-        [],
-    )?;
+/// Returns the current time, formatted like the timestamps of `FetchRun`
+pub(crate) fn now() -> anyhow::Result<String> {
+    // Formatted by SQLite itself, without touching the database file
+    let conn = sql::Connection::open_in_memory()?;
+    let now = conn.query_row("SELECT strftime('%Y-%m-%dT%H:%M:%SZ', 'now')", [], |row| {
+        row.get(0)
+    })?;
+    Ok(now)
+}
 
+/// Records a new run, started at `started_at`, and returns its id
+pub(crate) fn start_run(
+    conn: &sql::Connection,
+    group_path: &str,
+    started_at: &str,
+    updated_after: Option<&str>,
+    range: &DateRange,
+) -> anyhow::Result<i64> {
     conn.execute(
-        "CREATE TABLE Milestone (id INTEGER NOT NULL PRIMARY KEY, name VARCHAR)",
-        [],
+        "INSERT INTO FetchRun (group_path, started_at, updated_after, since, until)
+            VALUES (?, ?, ?, ?, ?)",
+        sql::params![
+            group_path,
+            started_at,
+            updated_after,
+            range.since,
+            range.until
+        ],
     )?;
+    Ok(conn.last_insert_rowid())
+}
 
-    conn.execute(
-        "CREATE TABLE Issue (
-            id INTEGER NOT NULL PRIMARY KEY,
-            iid INTEGER NOT NULL,
-            project_id INTEGER NOT NULL,
-            milestone_id INTEGER,
-            name VARCHAR,
-            CONSTRAINT fk_project_id FOREIGN KEY (project_id) REFERENCES Project (id),
-            CONSTRAINT fk_milestone_id FOREIGN KEY (milestone_id) REFERENCES Milestone (id)
-        )",
-        [],
-    )?;
+/// Upserts the fetched data and marks the run `run_id` as finished
+pub(crate) fn save_to_db(
+    conn: &mut sql::Connection,
+    run_id: i64,
+    range: &DateRange,
+    result: FetchResult,
+) -> anyhow::Result<()> {
+    let FetchResult {
+        groups,
+        projects,
+        milestones,
+        epics,
+        iteration_cadences,
+        iterations,
+        issues,
+        merge_requests,
+        labels,
+        time_logs,
+        users,
+        memberships,
+    } = result;
 
-    conn.execute(
-        "CREATE TABLE MergeRequest (
-            id INTEGER NOT NULL PRIMARY KEY,
-            iid INTEGER NOT NULL,
-            project_id INTEGER NOT NULL,
-            milestone_id INTEGER,
-            name VARCHAR,
-            CONSTRAINT fk_project_id FOREIGN KEY (project_id) REFERENCES Project (id),
-            CONSTRAINT fk_milestone_id FOREIGN KEY (milestone_id) REFERENCES Milestone (id)
-        )",
-        [],
+    let tx = conn.transaction()?;
+    insert_users(&tx, &users)?;
+    insert_groups(&tx, &groups)?;
+    insert_projects(&tx, &projects)?;
+    replace_memberships(&tx, &groups, &projects, &memberships)?;
+    insert_milestones(&tx, &milestones)?;
+    insert_epics(&tx, &epics)?;
+    insert_iteration_cadences(&tx, &iteration_cadences)?;
+    insert_iterations(&tx, &iterations)?;
+    insert_labels(&tx, &labels)?;
+    insert_issues(&tx, &issues)?;
+    insert_merge_requests(&tx, &merge_requests)?;
+    delete_time_logs(&tx, range, &issues, &merge_requests)?;
+    insert_time_logs(&tx, &time_logs)?;
+    tx.execute(
+        "UPDATE FetchRun SET finished_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?",
+        [run_id],
     )?;
+    tx.commit()?;
 
-    conn.execute(
-        "CREATE TABLE TimeLog (
-            time INTEGER NOT NULL,
-            date VARCHAR NOT NULL,
-            user_id INTEGER NOT NULL,
-            issue_id INTEGER,
-            merge_request_id INTEGER,
-            PRIMARY KEY (user_id, date),
-            CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES User (id),
-            CONSTRAINT fk_issue_id FOREIGN KEY (issue_id) REFERENCES Issue (id),
-            CONSTRAINT fk_merge_request_id FOREIGN KEY (merge_request_id) REFERENCES MergeRequest (id)
-        )",
-        []
-    )?;
     Ok(())
 }
 
+/// Returns the schema version of the database.
+///
+/// Databases written before schema versioning was introduced have no version set, so their version
+/// is derived from the tables they contain.
+fn schema_version(conn: &sql::Connection) -> anyhow::Result<usize> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > 0 {
+        return Ok(version);
+    }
+
+    let has_table = |table: &str| -> sql::Result<bool> {
+        conn.query_row(
+            "SELECT count(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?",
+            [table],
+            |row| row.get(0),
+        )
+    };
+    let has_column = |table: &str, column: &str| -> sql::Result<bool> {
+        conn.query_row(
+            "SELECT count(*) > 0 FROM pragma_table_info(?) WHERE name = ?",
+            [table, column],
+            |row| row.get(0),
+        )
+    };
+
+    let version = if !has_table("TimeLog")? {
+        0
+    } else if has_table("FetchRun")? {
+        4
+    } else if has_column("TimeLog", "seconds")? {
+        3
+    } else if has_table("Group")? {
+        2
+    } else {
+        1
+    };
+    Ok(version)
+}
+
+/// Applies all pending migrations and returns the schema versions before and after migrating
+pub(crate) fn migrate(conn: &mut sql::Connection) -> anyhow::Result<(usize, usize)> {
+    let from = schema_version(conn)?;
+    if from > SCHEMA_VERSION {
+        return Err(DbError::DbError(format!(
+            "Database has schema version {}, but only version {} is supported",
+            from, SCHEMA_VERSION
+        ))
+        .into());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(from) {
+        let version = index + 1;
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)
+            .with_context(|| format!("Migrate database to schema version {}", version))?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+
+    Ok((from, SCHEMA_VERSION))
+}
+
 fn insert_users(conn: &sql::Connection, users: &Vec<User>) -> anyhow::Result<()> {
     for user in users {
+        // A placeholder must not replace a user that was resolved on an earlier run
+        let statement = if user.placeholder {
+            "INSERT OR IGNORE INTO User (id, username, name, public_email, state, bot, avatar_url, is_placeholder) VALUES (?,?,?,?,?,?,?,?)"
+        } else {
+            "INSERT OR REPLACE INTO User (id, username, name, public_email, state, bot, avatar_url, is_placeholder) VALUES (?,?,?,?,?,?,?,?)"
+        };
         conn.execute(
-            "INSERT INTO User VALUES (?,?)",
-            sql::params![user.id, user.username],
+            statement,
+            sql::params![
+                user.id,
+                user.username,
+                user.name,
+                user.public_email,
+                user.state,
+                user.bot,
+                user.avatar_url,
+                user.placeholder,
+            ],
         )
         .with_context(|| format!("Insert user {:?}", user))?;
     }
     Ok(())
 }
 
+/// Replaces the memberships of `groups` and `projects` with `memberships`
+fn replace_memberships(
+    conn: &sql::Connection,
+    groups: &Vec<Group>,
+    projects: &Vec<Project>,
+    memberships: &Vec<Membership>,
+) -> anyhow::Result<()> {
+    for group in groups {
+        conn.execute("DELETE FROM Membership WHERE group_id = ?", [group.id])
+            .with_context(|| format!("Delete memberships of group {:?}", group))?;
+    }
+    for project in projects {
+        conn.execute("DELETE FROM Membership WHERE project_id = ?", [project.id])
+            .with_context(|| format!("Delete memberships of project {:?}", project))?;
+    }
+    for membership in memberships {
+        conn.execute(
+            "INSERT INTO Membership (
+                user_id, group_id, project_id, access_level, access_level_name, inherited,
+                created_at, expires_at
+            ) VALUES (?,?,?,?,?,?,?,?)",
+            sql::params![
+                membership.user_id,
+                membership.group_id,
+                membership.project_id,
+                membership.access_level,
+                membership.access_level_name.map(|level| level.as_str()),
+                membership.inherited,
+                membership.created_at,
+                membership.expires_at,
+            ],
+        )
+        .with_context(|| format!("Insert membership {:?}", membership))?;
+    }
+    Ok(())
+}
+
+fn insert_groups(conn: &sql::Connection, groups: &Vec<Group>) -> anyhow::Result<()> {
+    for group in groups {
+        conn.execute(
+            "INSERT OR REPLACE INTO \"Group\" (id, name, full_path) VALUES (?,?,?)",
+            sql::params![group.id, group.name, group.full_path],
+        )
+        .with_context(|| format!("Insert group {:?}", group))?;
+    }
+    // A group moved into another one may have a lower id than its new parent, so parents are only
+    // linked once all groups are stored
+    for group in groups {
+        conn.execute(
+            "UPDATE \"Group\" SET parent_id = ? WHERE id = ?",
+            sql::params![group.parent_id, group.id],
+        )
+        .with_context(|| format!("Link parent of group {:?}", group))?;
+    }
+    Ok(())
+}
+
 fn insert_projects(conn: &sql::Connection, projects: &Vec<Project>) -> anyhow::Result<()> {
     for project in projects {
         conn.execute(
-            "INSERT INTO Project VALUES (?,?)",
-            sql::params![project.id, project.name],
+            "INSERT OR REPLACE INTO Project (id, name, full_path, web_url, group_id)
+                VALUES (?,?,?,?,?)",
+            sql::params![
+                project.id,
+                project.name,
+                project.full_path,
+                project.web_url,
+                project.group_id
+            ],
         )
         .with_context(|| format!("Insert project {:?}", project))?;
     }
@@ -108,47 +283,340 @@ fn insert_projects(conn: &sql::Connection, projects: &Vec<Project>) -> anyhow::R
 fn insert_milestones(conn: &sql::Connection, milestones: &Vec<Milestone>) -> anyhow::Result<()> {
     for milestone in milestones {
         conn.execute(
-            "INSERT INTO Milestone VALUES (?,?)",
-            sql::params![milestone.id, milestone.name],
+            "INSERT OR REPLACE INTO Milestone (
+                id, name, description, state, start_date, due_date, web_url, group_id, project_id
+            ) VALUES (?,?,?,?,?,?,?,?,?)",
+            sql::params![
+                milestone.id,
+                milestone.name,
+                milestone.description,
+                milestone.state.as_str(),
+                milestone.start_date,
+                milestone.due_date,
+                milestone.web_url,
+                milestone.group_id,
+                milestone.project_id,
+            ],
         )
         .with_context(|| format!("Insert milestone {:?}", milestone))?;
     }
     Ok(())
 }
 
+/// Upserts `epics`. Parents are linked in a second pass, as they may come after their children.
+fn insert_epics(conn: &sql::Connection, epics: &Vec<Epic>) -> anyhow::Result<()> {
+    for epic in epics {
+        conn.execute(
+            "INSERT OR REPLACE INTO Epic (
+                id, iid, name, group_id, state, start_date, due_date, created_at, closed_at, web_url
+            ) VALUES (?,?,?,?,?,?,?,?,?,?)",
+            sql::params![
+                epic.id,
+                epic.iid,
+                epic.name,
+                epic.group_id,
+                epic.state,
+                epic.start_date,
+                epic.due_date,
+                epic.created_at,
+                epic.closed_at,
+                epic.web_url,
+            ],
+        )
+        .with_context(|| format!("Insert epic {:?}", epic))?;
+    }
+    for epic in epics {
+        conn.execute(
+            "UPDATE Epic SET parent_id = ? WHERE id = ?",
+            sql::params![epic.parent_id, epic.id],
+        )
+        .with_context(|| format!("Link parent of epic {:?}", epic))?;
+    }
+    Ok(())
+}
+
+fn insert_iteration_cadences(
+    conn: &sql::Connection,
+    cadences: &Vec<IterationCadence>,
+) -> anyhow::Result<()> {
+    for cadence in cadences {
+        conn.execute(
+            "INSERT OR REPLACE INTO IterationCadence (
+                id, name, description, duration_in_weeks, automatic, active, start_date
+            ) VALUES (?,?,?,?,?,?,?)",
+            sql::params![
+                cadence.id,
+                cadence.name,
+                cadence.description,
+                cadence.duration_in_weeks,
+                cadence.automatic,
+                cadence.active,
+                cadence.start_date,
+            ],
+        )
+        .with_context(|| format!("Insert iteration cadence {:?}", cadence))?;
+    }
+    Ok(())
+}
+
+fn insert_iterations(conn: &sql::Connection, iterations: &Vec<Iteration>) -> anyhow::Result<()> {
+    for iteration in iterations {
+        conn.execute(
+            "INSERT OR REPLACE INTO Iteration (
+                id, iid, name, description, state, start_date, due_date, web_url, cadence_id
+            ) VALUES (?,?,?,?,?,?,?,?,?)",
+            sql::params![
+                iteration.id,
+                iteration.iid,
+                iteration.name,
+                iteration.description,
+                iteration.state,
+                iteration.start_date,
+                iteration.due_date,
+                iteration.web_url,
+                iteration.cadence_id,
+            ],
+        )
+        .with_context(|| format!("Insert iteration {:?}", iteration))?;
+    }
+    Ok(())
+}
+
+fn insert_labels(conn: &sql::Connection, labels: &Vec<Label>) -> anyhow::Result<()> {
+    for label in labels {
+        let (scope, value) = label.scope_and_value().unzip();
+        conn.execute(
+            "INSERT OR REPLACE INTO Label (id, title, color, description, scope, value) VALUES (?,?,?,?,?,?)",
+            sql::params![
+                label.id,
+                label.title,
+                label.color,
+                label.description,
+                scope,
+                value,
+            ],
+        )
+        .with_context(|| format!("Insert label {:?}", label))?;
+    }
+    Ok(())
+}
+
+/// Upserts `issues` and replaces their labels, assignees, parents and links
 fn insert_issues(conn: &sql::Connection, issues: &Vec<Issue>) -> anyhow::Result<()> {
     for issue in issues {
         conn.execute(
-            "INSERT INTO Issue VALUES (?,?,?,?,?)",
+            "INSERT OR REPLACE INTO Issue (
+                id, iid, project_id, milestone_id, epic_id, iteration_id, name, type, state,
+                created_at, closed_at, time_estimate, total_time_spent, weight, due_date, web_url,
+                author_id
+            ) VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)",
             sql::params![
                 issue.id,
                 issue.iid,
                 issue.project_id,
                 issue.milestone_id,
-                issue.name
+                issue.epic_id,
+                issue.iteration_id,
+                issue.name,
+                issue.issue_type,
+                issue.state,
+                issue.created_at,
+                issue.closed_at,
+                issue.time_estimate,
+                issue.total_time_spent,
+                issue.weight,
+                issue.due_date,
+                issue.web_url,
+                issue.author_id,
             ],
         )
         .with_context(|| format!("Insert issue {:?}", issue))?;
+
+        conn.execute("DELETE FROM IssueLabel WHERE issue_id = ?", [issue.id])
+            .with_context(|| format!("Delete labels of issue {:?}", issue))?;
+        for label in &issue.labels {
+            conn.execute(
+                "INSERT INTO IssueLabel (issue_id, label_id) VALUES (?,?)",
+                [issue.id, label.id],
+            )
+            .with_context(|| format!("Insert label {:?} of issue {:?}", label, issue))?;
+        }
+
+        conn.execute("DELETE FROM IssueAssignee WHERE issue_id = ?", [issue.id])
+            .with_context(|| format!("Delete assignees of issue {:?}", issue))?;
+        for assignee_id in &issue.assignee_ids {
+            conn.execute(
+                "INSERT INTO IssueAssignee (issue_id, user_id) VALUES (?,?)",
+                [issue.id, *assignee_id],
+            )
+            .with_context(|| format!("Insert assignee {} of issue {:?}", assignee_id, issue))?;
+        }
+    }
+    // Parents and linked issues may be part of `issues` or stored by an earlier run. Those outside
+    // of the crawled projects are not stored, so they cannot be referenced.
+    for issue in issues {
+        conn.execute(
+            "UPDATE Issue SET parent_id = (SELECT id FROM Issue WHERE id = ?) WHERE id = ?",
+            sql::params![issue.parent_id, issue.id],
+        )
+        .with_context(|| format!("Link parent of issue {:?}", issue))?;
+
+        // Keep the links of an earlier run if they were not fetched this time
+        let Some(links) = &issue.links else {
+            continue;
+        };
+        conn.execute("DELETE FROM IssueLink WHERE issue_id = ?", [issue.id])
+            .with_context(|| format!("Delete links of issue {:?}", issue))?;
+        for link in links {
+            conn.execute(
+                "INSERT INTO IssueLink (issue_id, linked_issue_id, link_type)
+                SELECT ?, id, ? FROM Issue WHERE id = ?",
+                sql::params![issue.id, link.link_type, link.issue_id],
+            )
+            .with_context(|| format!("Insert link {:?} of issue {:?}", link, issue))?;
+        }
     }
     Ok(())
 }
 
+/// Upserts `merge_requests` and replaces their labels, assignees and reviewers
 fn insert_merge_requests(
     conn: &sql::Connection,
     merge_requests: &Vec<MergeRequest>,
 ) -> anyhow::Result<()> {
     for merge_request in merge_requests {
         conn.execute(
-            "INSERT INTO MergeRequest VALUES (?,?,?,?,?)",
+            "INSERT OR REPLACE INTO MergeRequest (
+                id, iid, project_id, milestone_id, name, state, merged_at, source_branch,
+                target_branch, web_url, author_id
+            ) VALUES (?,?,?,?,?,?,?,?,?,?,?)",
             sql::params![
                 merge_request.id,
                 merge_request.iid,
                 merge_request.project_id,
                 merge_request.milestone_id,
-                merge_request.name
+                merge_request.name,
+                merge_request.state,
+                merge_request.merged_at,
+                merge_request.source_branch,
+                merge_request.target_branch,
+                merge_request.web_url,
+                merge_request.author_id,
             ],
         )
         .with_context(|| format!("Insert merge request {:?}", merge_request))?;
+
+        conn.execute(
+            "DELETE FROM MergeRequestLabel WHERE merge_request_id = ?",
+            [merge_request.id],
+        )
+        .with_context(|| format!("Delete labels of merge request {:?}", merge_request))?;
+        for label in &merge_request.labels {
+            conn.execute(
+                "INSERT INTO MergeRequestLabel (merge_request_id, label_id) VALUES (?,?)",
+                [merge_request.id, label.id],
+            )
+            .with_context(|| {
+                format!(
+                    "Insert label {:?} of merge request {:?}",
+                    label, merge_request
+                )
+            })?;
+        }
+
+        conn.execute(
+            "DELETE FROM MergeRequestAssignee WHERE merge_request_id = ?",
+            [merge_request.id],
+        )
+        .with_context(|| format!("Delete assignees of merge request {:?}", merge_request))?;
+        for assignee_id in &merge_request.assignee_ids {
+            conn.execute(
+                "INSERT INTO MergeRequestAssignee (merge_request_id, user_id) VALUES (?,?)",
+                [merge_request.id, *assignee_id],
+            )
+            .with_context(|| {
+                format!(
+                    "Insert assignee {} of merge request {:?}",
+                    assignee_id, merge_request
+                )
+            })?;
+        }
+
+        conn.execute(
+            "DELETE FROM MergeRequestReviewer WHERE merge_request_id = ?",
+            [merge_request.id],
+        )
+        .with_context(|| format!("Delete reviewers of merge request {:?}", merge_request))?;
+        for reviewer_id in &merge_request.reviewer_ids {
+            conn.execute(
+                "INSERT INTO MergeRequestReviewer (merge_request_id, user_id) VALUES (?,?)",
+                [merge_request.id, *reviewer_id],
+            )
+            .with_context(|| {
+                format!(
+                    "Insert reviewer {} of merge request {:?}",
+                    reviewer_id, merge_request
+                )
+            })?;
+        }
+
+        // Keep the issues of an earlier run if they were not fetched this time
+        let Some(issue_ids) = &merge_request.issue_ids else {
+            continue;
+        };
+        conn.execute(
+            "DELETE FROM MergeRequestIssue WHERE merge_request_id = ?",
+            [merge_request.id],
+        )
+        .with_context(|| format!("Delete issues of merge request {:?}", merge_request))?;
+        let issue_ids = issue_ids
+            .closes
+            .iter()
+            .map(|id| (id, "closes"))
+            .chain(issue_ids.related.iter().map(|id| (id, "related")));
+        for (issue_id, relation) in issue_ids {
+            // Issues outside of the crawled projects are not stored, so they cannot be linked
+            conn.execute(
+                "INSERT INTO MergeRequestIssue (merge_request_id, issue_id, relation)
+                SELECT ?, id, ? FROM Issue WHERE id = ?",
+                sql::params![merge_request.id, relation, issue_id],
+            )
+            .with_context(|| {
+                format!(
+                    "Insert issue {} of merge request {:?}",
+                    issue_id, merge_request
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes all stored time logs of `issues` and `merge_requests` within `range`, so time logs
+/// deleted at GitLab do not survive a sync
+fn delete_time_logs(
+    conn: &sql::Connection,
+    range: &DateRange,
+    issues: &Vec<Issue>,
+    merge_requests: &Vec<MergeRequest>,
+) -> anyhow::Result<()> {
+    for issue in issues {
+        conn.execute(
+            "DELETE FROM TimeLog WHERE issue_id = ?1
+                AND (?2 IS NULL OR substr(date, 1, 10) >= ?2)
+                AND (?3 IS NULL OR substr(date, 1, 10) <= ?3)",
+            sql::params![issue.id, range.since, range.until],
+        )
+        .with_context(|| format!("Delete time logs of issue {:?}", issue))?;
+    }
+    for merge_request in merge_requests {
+        conn.execute(
+            "DELETE FROM TimeLog WHERE merge_request_id = ?1
+                AND (?2 IS NULL OR substr(date, 1, 10) >= ?2)
+                AND (?3 IS NULL OR substr(date, 1, 10) <= ?3)",
+            sql::params![merge_request.id, range.since, range.until],
+        )
+        .with_context(|| format!("Delete time logs of merge request {:?}", merge_request))?;
     }
     Ok(())
 }
@@ -156,9 +624,19 @@ fn insert_merge_requests(
 fn insert_time_logs(conn: &sql::Connection, time_logs: &Vec<TimeLog>) -> anyhow::Result<()> {
     for time_log in time_logs {
         conn.execute(
-            "INSERT INTO TimeLog VALUES (?,?,?,?,?)",
+            "INSERT OR REPLACE INTO TimeLog (
+                id, seconds, minutes, hours, is_negative, summary, note_id, note_created_at, date,
+                user_id, issue_id, merge_request_id
+            ) VALUES (?,?,?,?,?,?,?,?,?,?,?,?)",
             sql::params![
-                time_log.time,
+                time_log.id,
+                time_log.seconds,
+                time_log.minutes(),
+                time_log.hours(),
+                time_log.is_negative(),
+                time_log.summary,
+                time_log.note_id,
+                time_log.note_created_at,
                 time_log.date,
                 time_log.user_id,
                 time_log.issue_id,
@@ -169,3 +647,127 @@ fn insert_time_logs(conn: &sql::Connection, time_logs: &Vec<TimeLog>) -> anyhow:
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an in-memory database with the schema of version 1, which predates schema versioning
+    fn baseline_db() -> sql::Connection {
+        let conn = sql::Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO User (id, username) VALUES (1, 'alice');
+            INSERT INTO Project (id, name) VALUES (1, 'project');
+            INSERT INTO Issue (id, iid, project_id, name)
+                VALUES (1, 1, 1, 'parent'), (2, 2, 1, 'child');
+            INSERT INTO TimeLog (time, date, user_id, issue_id)
+                VALUES (90, '2024-01-01', 1, 1), (30, '2024-01-02', 1, 2);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn schema_version_of_unversioned_databases() {
+        for version in 0..=4 {
+            let conn = sql::Connection::open_in_memory().unwrap();
+            for migration in &MIGRATIONS[..version] {
+                conn.execute_batch(migration).unwrap();
+            }
+            assert_eq!(schema_version(&conn).unwrap(), version);
+        }
+    }
+
+    #[test]
+    fn migrate_baseline_database() {
+        let mut conn = baseline_db();
+
+        assert_eq!(migrate(&mut conn).unwrap(), (1, SCHEMA_VERSION));
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        let time_logs: Vec<(i64, i64, f64)> = conn
+            .prepare("SELECT id, seconds, hours FROM TimeLog ORDER BY date")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<sql::Result<_>>()
+            .unwrap();
+        // Time logs without GitLab id get negative ids derived from their rowid
+        assert_eq!(time_logs, vec![(-1, 5400, 1.5), (-2, 1800, 0.5)]);
+
+        let issue_time: (f64, f64) = conn
+            .query_row(
+                "SELECT seconds, total_seconds FROM IssueTime WHERE issue_id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(issue_time, (5400.0, 5400.0));
+
+        conn.execute("UPDATE Issue SET parent_id = 1 WHERE id = 2", [])
+            .unwrap();
+        let issue_tree_time: (f64, f64) = conn
+            .query_row(
+                "SELECT seconds, total_seconds FROM IssueTreeTime WHERE issue_id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(issue_tree_time, (5400.0, 7200.0));
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut conn = baseline_db();
+        migrate(&mut conn).unwrap();
+
+        assert_eq!(
+            migrate(&mut conn).unwrap(),
+            (SCHEMA_VERSION, SCHEMA_VERSION)
+        );
+    }
+
+    #[test]
+    fn merge_request_time_is_split_between_closed_issues() {
+        let mut conn = baseline_db();
+        migrate(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO Issue (id, iid, project_id, name) VALUES (3, 3, 1, 'mentioned');
+            INSERT INTO MergeRequest (id, iid, project_id, name) VALUES (1, 1, 1, 'fix');
+            INSERT INTO TimeLog
+                (id, seconds, minutes, hours, is_negative, date, user_id, merge_request_id)
+                VALUES (1, 3600, 60, 1, 0, '2024-01-03', 1, 1);
+            INSERT INTO MergeRequestIssue (merge_request_id, issue_id, relation)
+                VALUES (1, 1, 'closes'), (1, 2, 'closes'), (1, 3, 'related');",
+        )
+        .unwrap();
+
+        let issue_time: Vec<(i64, f64, f64)> = conn
+            .prepare(
+                "SELECT issue_id, merge_request_seconds, total_seconds
+                FROM IssueTime ORDER BY issue_id",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<sql::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            issue_time,
+            vec![(1, 1800.0, 7200.0), (2, 1800.0, 3600.0), (3, 0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn migrate_rejects_newer_databases() {
+        let mut conn = sql::Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+
+        assert!(migrate(&mut conn).is_err());
+    }
+}
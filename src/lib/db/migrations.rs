@@ -0,0 +1,327 @@
+//! Ordered list of all schema migrations.
+//!
+//! The schema version of a database (`PRAGMA user_version`) is the number of migrations applied to
+//! it. Released migrations must never be changed; schema changes are appended as a new migration.
+
+pub(super) const MIGRATIONS: &[&str] = &[
+    // 1: Initial schema
+    "CREATE TABLE User (id INTEGER NOT NULL PRIMARY KEY, username VARCHAR);
+
+    CREATE TABLE Project (id INTEGER NOT NULL PRIMARY KEY, name VARCHAR);
+
+    CREATE TABLE Milestone (id INTEGER NOT NULL PRIMARY KEY, name VARCHAR);
+
+    CREATE TABLE Issue (
+        id INTEGER NOT NULL PRIMARY KEY,
+        iid INTEGER NOT NULL,
+        project_id INTEGER NOT NULL,
+        milestone_id INTEGER,
+        name VARCHAR,
+        CONSTRAINT fk_project_id FOREIGN KEY (project_id) REFERENCES Project (id),
+        CONSTRAINT fk_milestone_id FOREIGN KEY (milestone_id) REFERENCES Milestone (id)
+    );
+
+    CREATE TABLE MergeRequest (
+        id INTEGER NOT NULL PRIMARY KEY,
+        iid INTEGER NOT NULL,
+        project_id INTEGER NOT NULL,
+        milestone_id INTEGER,
+        name VARCHAR,
+        CONSTRAINT fk_project_id FOREIGN KEY (project_id) REFERENCES Project (id),
+        CONSTRAINT fk_milestone_id FOREIGN KEY (milestone_id) REFERENCES Milestone (id)
+    );
+
+    CREATE TABLE TimeLog (
+        time INTEGER NOT NULL,
+        date VARCHAR NOT NULL,
+        user_id INTEGER NOT NULL,
+        issue_id INTEGER,
+        merge_request_id INTEGER,
+        PRIMARY KEY (user_id, date),
+        CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES User (id),
+        CONSTRAINT fk_issue_id FOREIGN KEY (issue_id) REFERENCES Issue (id),
+        CONSTRAINT fk_merge_request_id FOREIGN KEY (merge_request_id) REFERENCES MergeRequest (id)
+    );",
+    // 2: Subgroups and project paths
+    "CREATE TABLE \"Group\" (
+        id INTEGER NOT NULL PRIMARY KEY,
+        name VARCHAR,
+        full_path VARCHAR,
+        parent_id INTEGER,
+        CONSTRAINT fk_parent_id FOREIGN KEY (parent_id) REFERENCES \"Group\" (id)
+    );
+
+    ALTER TABLE Project ADD COLUMN full_path VARCHAR;
+    ALTER TABLE Project ADD COLUMN web_url VARCHAR;
+    ALTER TABLE Project ADD COLUMN group_id INTEGER
+        CONSTRAINT fk_group_id REFERENCES \"Group\" (id);",
+    // 3: One row per GitLab time log, with the time spent in seconds.
+    //    Time logs of older databases have no GitLab id, so they get negative ids which can never
+    //    collide with the ids of newly fetched time logs.
+    "CREATE TABLE TimeLog_new (
+        id INTEGER NOT NULL PRIMARY KEY,
+        seconds INTEGER NOT NULL,
+        minutes REAL NOT NULL,
+        hours REAL NOT NULL,
+        is_negative BOOLEAN NOT NULL,
+        date VARCHAR NOT NULL,
+        user_id INTEGER NOT NULL,
+        issue_id INTEGER,
+        merge_request_id INTEGER,
+        CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES User (id),
+        CONSTRAINT fk_issue_id FOREIGN KEY (issue_id) REFERENCES Issue (id),
+        CONSTRAINT fk_merge_request_id FOREIGN KEY (merge_request_id) REFERENCES MergeRequest (id)
+    );
+
+    INSERT INTO TimeLog_new
+        SELECT -rowid, time * 60, time, time / 60.0, time < 0, date, user_id, issue_id, merge_request_id
+        FROM TimeLog;
+
+    DROP TABLE TimeLog;
+
+    ALTER TABLE TimeLog_new RENAME TO TimeLog;",
+    // 4: Fetch runs, used for syncing
+    "CREATE TABLE FetchRun (
+        id INTEGER NOT NULL PRIMARY KEY,
+        group_path VARCHAR NOT NULL,
+        started_at VARCHAR NOT NULL,
+        finished_at VARCHAR,
+        updated_after VARCHAR
+    );",
+    // 5: Time log date range of fetch runs
+    "ALTER TABLE FetchRun ADD COLUMN since VARCHAR;
+    ALTER TABLE FetchRun ADD COLUMN until VARCHAR;",
+    // 6: Time log summaries and notes
+    "ALTER TABLE TimeLog ADD COLUMN summary VARCHAR;
+    ALTER TABLE TimeLog ADD COLUMN note_id INTEGER;
+    ALTER TABLE TimeLog ADD COLUMN note_created_at VARCHAR;",
+    // 7: Issue metadata, labels and assignees
+    "ALTER TABLE Issue ADD COLUMN state VARCHAR;
+    ALTER TABLE Issue ADD COLUMN created_at VARCHAR;
+    ALTER TABLE Issue ADD COLUMN closed_at VARCHAR;
+    ALTER TABLE Issue ADD COLUMN time_estimate INTEGER;
+    ALTER TABLE Issue ADD COLUMN total_time_spent INTEGER;
+    ALTER TABLE Issue ADD COLUMN weight INTEGER;
+    ALTER TABLE Issue ADD COLUMN due_date VARCHAR;
+    ALTER TABLE Issue ADD COLUMN web_url VARCHAR;
+    ALTER TABLE Issue ADD COLUMN author_id INTEGER
+        CONSTRAINT fk_author_id REFERENCES User (id);
+
+    CREATE TABLE Label (id INTEGER NOT NULL PRIMARY KEY, title VARCHAR NOT NULL);
+
+    CREATE TABLE IssueLabel (
+        issue_id INTEGER NOT NULL,
+        label_id INTEGER NOT NULL,
+        PRIMARY KEY (issue_id, label_id),
+        CONSTRAINT fk_issue_id FOREIGN KEY (issue_id) REFERENCES Issue (id),
+        CONSTRAINT fk_label_id FOREIGN KEY (label_id) REFERENCES Label (id)
+    );
+
+    CREATE TABLE IssueAssignee (
+        issue_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        PRIMARY KEY (issue_id, user_id),
+        CONSTRAINT fk_issue_id FOREIGN KEY (issue_id) REFERENCES Issue (id),
+        CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES User (id)
+    );",
+    // 8: Merge request metadata, labels, assignees and reviewers
+    "ALTER TABLE MergeRequest ADD COLUMN state VARCHAR;
+    ALTER TABLE MergeRequest ADD COLUMN merged_at VARCHAR;
+    ALTER TABLE MergeRequest ADD COLUMN source_branch VARCHAR;
+    ALTER TABLE MergeRequest ADD COLUMN target_branch VARCHAR;
+    ALTER TABLE MergeRequest ADD COLUMN web_url VARCHAR;
+    ALTER TABLE MergeRequest ADD COLUMN author_id INTEGER
+        CONSTRAINT fk_author_id REFERENCES User (id);
+
+    CREATE TABLE MergeRequestLabel (
+        merge_request_id INTEGER NOT NULL,
+        label_id INTEGER NOT NULL,
+        PRIMARY KEY (merge_request_id, label_id),
+        CONSTRAINT fk_merge_request_id FOREIGN KEY (merge_request_id) REFERENCES MergeRequest (id),
+        CONSTRAINT fk_label_id FOREIGN KEY (label_id) REFERENCES Label (id)
+    );
+
+    CREATE TABLE MergeRequestAssignee (
+        merge_request_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        PRIMARY KEY (merge_request_id, user_id),
+        CONSTRAINT fk_merge_request_id FOREIGN KEY (merge_request_id) REFERENCES MergeRequest (id),
+        CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES User (id)
+    );
+
+    CREATE TABLE MergeRequestReviewer (
+        merge_request_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        PRIMARY KEY (merge_request_id, user_id),
+        CONSTRAINT fk_merge_request_id FOREIGN KEY (merge_request_id) REFERENCES MergeRequest (id),
+        CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES User (id)
+    );",
+    // 9: Milestone details and owners
+    "ALTER TABLE Milestone ADD COLUMN description VARCHAR;
+    ALTER TABLE Milestone ADD COLUMN state VARCHAR;
+    ALTER TABLE Milestone ADD COLUMN start_date VARCHAR;
+    ALTER TABLE Milestone ADD COLUMN due_date VARCHAR;
+    ALTER TABLE Milestone ADD COLUMN web_url VARCHAR;
+    ALTER TABLE Milestone ADD COLUMN group_id INTEGER
+        CONSTRAINT fk_group_id REFERENCES \"Group\" (id);
+    ALTER TABLE Milestone ADD COLUMN project_id INTEGER
+        CONSTRAINT fk_project_id REFERENCES Project (id);",
+    // 10: Epics and the time spent on them
+    "CREATE TABLE Epic (
+        id INTEGER NOT NULL PRIMARY KEY,
+        iid INTEGER NOT NULL,
+        name VARCHAR,
+        group_id INTEGER NOT NULL,
+        parent_id INTEGER,
+        state VARCHAR,
+        start_date VARCHAR,
+        due_date VARCHAR,
+        created_at VARCHAR,
+        closed_at VARCHAR,
+        web_url VARCHAR,
+        CONSTRAINT fk_group_id FOREIGN KEY (group_id) REFERENCES \"Group\" (id),
+        CONSTRAINT fk_parent_id FOREIGN KEY (parent_id) REFERENCES Epic (id)
+    );
+
+    ALTER TABLE Issue ADD COLUMN epic_id INTEGER
+        CONSTRAINT fk_epic_id REFERENCES Epic (id);
+
+    CREATE VIEW EpicTime AS
+        WITH RECURSIVE epic_tree(root_id, epic_id) AS (
+            SELECT id, id FROM Epic
+            UNION ALL
+            SELECT et.root_id, e.id
+            FROM epic_tree et
+            INNER JOIN Epic e ON e.parent_id = et.epic_id
+        )
+        SELECT
+            et.root_id AS epic_id,
+            total(CASE WHEN et.epic_id = et.root_id THEN t.seconds END) AS seconds,
+            total(CASE WHEN et.epic_id = et.root_id THEN t.hours END) AS hours,
+            total(t.seconds) AS total_seconds,
+            total(t.hours) AS total_hours
+        FROM epic_tree et
+        LEFT JOIN Issue i ON i.epic_id = et.epic_id
+        LEFT JOIN TimeLog t ON t.issue_id = i.id
+        GROUP BY et.root_id;",
+    // 11: Iterations
+    "CREATE TABLE IterationCadence (
+        id INTEGER NOT NULL PRIMARY KEY,
+        name VARCHAR,
+        description VARCHAR,
+        duration_in_weeks INTEGER,
+        automatic BOOLEAN,
+        active BOOLEAN,
+        start_date VARCHAR
+    );
+
+    CREATE TABLE Iteration (
+        id INTEGER NOT NULL PRIMARY KEY,
+        iid INTEGER NOT NULL,
+        name VARCHAR,
+        description VARCHAR,
+        state VARCHAR,
+        start_date VARCHAR,
+        due_date VARCHAR,
+        web_url VARCHAR,
+        cadence_id INTEGER NOT NULL,
+        CONSTRAINT fk_cadence_id FOREIGN KEY (cadence_id) REFERENCES IterationCadence (id)
+    );
+
+    ALTER TABLE Issue ADD COLUMN iteration_id INTEGER
+        CONSTRAINT fk_iteration_id REFERENCES Iteration (id);",
+    // 12: User metadata and placeholders for users that could not be resolved
+    "ALTER TABLE User ADD COLUMN name VARCHAR;
+    ALTER TABLE User ADD COLUMN public_email VARCHAR;
+    ALTER TABLE User ADD COLUMN state VARCHAR;
+    ALTER TABLE User ADD COLUMN bot BOOLEAN NOT NULL DEFAULT FALSE;
+    ALTER TABLE User ADD COLUMN avatar_url VARCHAR;
+    ALTER TABLE User ADD COLUMN is_placeholder BOOLEAN NOT NULL DEFAULT FALSE;",
+    // 13: Label catalogue and scoped labels
+    "ALTER TABLE Label ADD COLUMN color VARCHAR;
+    ALTER TABLE Label ADD COLUMN description VARCHAR;
+    ALTER TABLE Label ADD COLUMN scope VARCHAR;
+    ALTER TABLE Label ADD COLUMN value VARCHAR;",
+    // 14: Issues closed by or related to merge requests, and the time spent on issues including
+    // their merge requests
+    "CREATE TABLE MergeRequestIssue (
+        merge_request_id INTEGER NOT NULL,
+        issue_id INTEGER NOT NULL,
+        relation VARCHAR NOT NULL,
+        PRIMARY KEY (merge_request_id, issue_id),
+        CONSTRAINT fk_merge_request_id FOREIGN KEY (merge_request_id) REFERENCES MergeRequest (id),
+        CONSTRAINT fk_issue_id FOREIGN KEY (issue_id) REFERENCES Issue (id)
+    );
+
+    CREATE VIEW IssueTime AS
+        WITH closing(merge_request_id, issue_id, share) AS (
+            SELECT merge_request_id, issue_id, 1.0 / count(*) OVER (PARTITION BY merge_request_id)
+            FROM MergeRequestIssue
+            WHERE relation = 'closes'
+        ),
+        merge_request_time(issue_id, seconds) AS (
+            SELECT c.issue_id, total(t.seconds * c.share)
+            FROM closing c
+            INNER JOIN TimeLog t ON t.merge_request_id = c.merge_request_id
+            GROUP BY c.issue_id
+        )
+        SELECT
+            i.id AS issue_id,
+            total(t.seconds) AS seconds,
+            total(t.seconds) / 3600.0 AS hours,
+            coalesce(mt.seconds, 0) AS merge_request_seconds,
+            coalesce(mt.seconds, 0) / 3600.0 AS merge_request_hours,
+            total(t.seconds) + coalesce(mt.seconds, 0) AS total_seconds,
+            (total(t.seconds) + coalesce(mt.seconds, 0)) / 3600.0 AS total_hours
+        FROM Issue i
+        LEFT JOIN TimeLog t ON t.issue_id = i.id
+        LEFT JOIN merge_request_time mt ON mt.issue_id = i.id
+        GROUP BY i.id;",
+    // 15: Issue hierarchy and links, and the time spent on issues including their children
+    "ALTER TABLE Issue ADD COLUMN parent_id INTEGER
+        CONSTRAINT fk_parent_id REFERENCES Issue (id);
+
+    CREATE TABLE IssueLink (
+        issue_id INTEGER NOT NULL,
+        linked_issue_id INTEGER NOT NULL,
+        link_type VARCHAR NOT NULL,
+        PRIMARY KEY (issue_id, linked_issue_id),
+        CONSTRAINT fk_issue_id FOREIGN KEY (issue_id) REFERENCES Issue (id),
+        CONSTRAINT fk_linked_issue_id FOREIGN KEY (linked_issue_id) REFERENCES Issue (id)
+    );
+
+    CREATE VIEW IssueTreeTime AS
+        WITH RECURSIVE issue_tree(root_id, issue_id) AS (
+            SELECT id, id FROM Issue
+            UNION ALL
+            SELECT it.root_id, i.id
+            FROM issue_tree it
+            INNER JOIN Issue i ON i.parent_id = it.issue_id
+        )
+        SELECT
+            it.root_id AS issue_id,
+            total(CASE WHEN it.issue_id = it.root_id THEN t.seconds END) AS seconds,
+            total(CASE WHEN it.issue_id = it.root_id THEN t.hours END) AS hours,
+            total(t.seconds) AS total_seconds,
+            total(t.hours) AS total_hours
+        FROM issue_tree it
+        LEFT JOIN TimeLog t ON t.issue_id = it.issue_id
+        GROUP BY it.root_id;",
+    // 16: Issue types
+    "ALTER TABLE Issue ADD COLUMN type VARCHAR NOT NULL DEFAULT 'issue';",
+    // 17: Group and project members
+    "CREATE TABLE Membership (
+        id INTEGER NOT NULL PRIMARY KEY,
+        user_id INTEGER NOT NULL,
+        group_id INTEGER,
+        project_id INTEGER,
+        access_level INTEGER,
+        access_level_name VARCHAR,
+        inherited BOOLEAN NOT NULL DEFAULT FALSE,
+        created_at VARCHAR,
+        expires_at VARCHAR,
+        CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES User (id),
+        CONSTRAINT fk_group_id FOREIGN KEY (group_id) REFERENCES \"Group\" (id),
+        CONSTRAINT fk_project_id FOREIGN KEY (project_id) REFERENCES Project (id)
+    );",
+];
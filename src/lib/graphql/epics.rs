@@ -0,0 +1,99 @@
+use graphql_client::GraphQLQuery;
+
+use super::{Fetch, Fetcher};
+use crate::{graphql::Id, model::Epic, FetchError};
+
+type Time = String;
+
+// The paths are relative to the directory where your `Cargo.toml` is located.
+// Both json and the GraphQL schema language are supported as sources for the schema
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./group_epics_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct GroupEpicsQuery;
+
+/// Fetches all epics of a group.
+///
+/// If `include_subgroups` is set, epics of all descendant groups are included.
+impl Fetch<Vec<Epic>, (String, bool)> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        (full_path, include_subgroups): (String, bool),
+    ) -> anyhow::Result<Vec<Epic>> {
+        use group_epics_query as q;
+
+        let mut epics = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupEpicsQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                include_subgroups: Some(include_subgroups),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
+            let group = data
+                .group
+                .ok_or_else(|| FetchError::from("No group data on group epics query response"))?;
+            // Epics are not available on every GitLab tier
+            let Some(group_epics) = group.epics else {
+                break;
+            };
+
+            let has_next_page = group_epics.page_info.has_next_page;
+            let end_cursor = group_epics.page_info.end_cursor.clone();
+
+            for epic in group_epics.nodes.iter().flatten().flatten() {
+                let Id(id) = Id::try_from(epic.id.as_str())?;
+                let Id(group_id) = Id::try_from(epic.group.id.as_str())?;
+                let parent_id = match epic.parent.as_ref() {
+                    Some(parent) => {
+                        let Id(id) = Id::try_from(parent.id.as_str())?;
+                        Some(id)
+                    }
+                    None => None,
+                };
+                epics.push(Epic {
+                    id,
+                    iid: epic.iid.parse()?,
+                    name: epic.title.clone().unwrap_or_default(),
+                    group_id,
+                    parent_id,
+                    state: epic_state(&epic.state),
+                    start_date: epic.start_date.clone(),
+                    due_date: epic.due_date.clone(),
+                    created_at: epic.created_at.clone(),
+                    closed_at: epic.closed_at.clone(),
+                    web_url: epic.web_url.clone(),
+                });
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(epics)
+    }
+}
+
+fn epic_state(state: &group_epics_query::EpicState) -> String {
+    use group_epics_query::EpicState;
+
+    match state {
+        EpicState::opened => "opened",
+        EpicState::closed => "closed",
+        EpicState::all => "all",
+        EpicState::Other(state) => state,
+    }
+    .to_string()
+}
@@ -1,13 +1,14 @@
-use anyhow::Context;
-use graphql_client::{GraphQLQuery, Response};
+use graphql_client::GraphQLQuery;
 
 use super::{Fetch, Fetcher};
 use crate::{
     graphql::Id,
-    model::{Milestone, Project},
+    model::{Group, Milestone, MilestoneState as MilestoneStateEnum, Project},
     FetchError,
 };
 
+type Time = String;
+
 // The paths are relative to the directory where your `Cargo.toml` is located.
 // Both json and the GraphQL schema language are supported as sources for the schema
 #[derive(GraphQLQuery)]
@@ -17,93 +18,314 @@ use crate::{
     response_derives = "Debug",
     "Clone",
     "Default",
+    extern_enums("MilestoneStateEnum"),
     skip_serializing_none
 )]
 pub(crate) struct GroupQuery;
 
-impl Fetch<(Vec<Project>, Vec<Milestone>), String> for Fetcher {
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./subgroups_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct SubgroupsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./group_milestones_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    extern_enums("MilestoneStateEnum"),
+    skip_serializing_none
+)]
+pub(crate) struct GroupMilestonesQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./project_milestones_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    extern_enums("MilestoneStateEnum"),
+    skip_serializing_none
+)]
+pub(crate) struct ProjectMilestonesQuery;
+
+/// Fetches the group itself, its projects and the milestones of both.
+///
+/// If `include_subgroups` is set, projects (and their milestones) of all descendant groups are
+/// included, but not the milestones of the descendant groups themselves.
+impl Fetch<(Group, Vec<Project>, Vec<Milestone>), (String, bool)> for Fetcher {
     fn fetch(
         &self,
         client: &reqwest::blocking::Client,
-        full_path: String,
-    ) -> anyhow::Result<(Vec<Project>, Vec<Milestone>)> {
+        (full_path, include_subgroups): (String, bool),
+    ) -> anyhow::Result<(Group, Vec<Project>, Vec<Milestone>)> {
         use group_query as q;
 
-        let query = GroupQuery::build_query(q::Variables { full_path });
-        let resp = client
-            .post(&self.base_uri)
-            .json(&query)
-            .send()
-            .context("Cannot make API request")?;
-        let response_body: Response<q::ResponseData> =
-            resp.json().context("API respone is no valid JSON")?;
-        let data: q::ResponseData = response_body
-            .data
-            .ok_or_else(|| FetchError::FetchError("Group response is empty".to_string()))?;
-        let group = data
-            .group
-            .ok_or_else(|| FetchError::FetchError("Group response is empty".to_string()))?;
-        let projects = group.projects.edges.ok_or_else(|| {
-            FetchError::FetchError("Group response contains no projects".to_string())
-        })?;
-
-        let group_milestones: Vec<Milestone> = match group.milestones {
-            Some(ms) => ms
-                .edges
-                .unwrap_or_default()
-                .iter()
-                .flatten()
-                .flat_map(|edge| edge.node.as_ref())
-                .filter_map(|node| match Id::try_from(node.id.as_str()) {
-                    Ok(Id(id)) => Some(Milestone {
-                        id,
-                        name: node.title.clone(),
-                    }),
-                    _ => None,
-                })
-                .collect(),
-            None => Default::default(),
-        };
-
-        let project_milestones = projects
-            .iter()
-            .flatten()
-            .flat_map(|edge| &edge.node)
-            .flat_map(|node| {
-                let project_milestones = node.milestones.as_ref()?;
-                let nodes = project_milestones.nodes.as_ref()?;
-                let vec = nodes.iter().flatten().filter_map(|node| {
-                    match Id::try_from(node.id.as_str()) {
-                        Ok(Id(id)) => Some(Milestone {
+        let mut root_group = None;
+        let mut projects = vec![];
+        let mut milestones = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                include_subgroups: Some(include_subgroups),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
+            let group = data
+                .group
+                .ok_or_else(|| FetchError::FetchError("Group response is empty".to_string()))?;
+
+            if root_group.is_none() {
+                let Id(group_id) = Id::try_from(group.id.as_str())?;
+                // The parent of the fetched group is not part of the crawl, so it is not referenced
+                root_group = Some(Group {
+                    id: group_id,
+                    name: group.name.clone(),
+                    full_path: group.full_path.clone(),
+                    parent_id: None,
+                });
+            }
+
+            let has_next_page = group.projects.page_info.has_next_page;
+            let end_cursor = group.projects.page_info.end_cursor.clone();
+
+            let project_edges = group.projects.edges.ok_or_else(|| {
+                FetchError::FetchError("Group response contains no projects".to_string())
+            })?;
+
+            for node in project_edges.iter().flatten().flat_map(|edge| &edge.node) {
+                let Ok(Id(id)) = Id::try_from(node.id.as_str()) else {
+                    continue;
+                };
+
+                if let Some(project_milestones) = node.milestones.as_ref() {
+                    milestones.extend(
+                        project_milestones
+                            .nodes
+                            .iter()
+                            .flatten()
+                            .flatten()
+                            .filter_map(|node| milestone!(node, self, None, Some(id))),
+                    );
+
+                    if project_milestones.page_info.has_next_page {
+                        milestones.extend(self.fetch_project_milestones(
+                            client,
+                            node.id.clone(),
                             id,
-                            name: node.title.clone(),
-                        }),
-                        _ => None,
+                            project_milestones.page_info.end_cursor.clone(),
+                        )?);
                     }
-                });
-                Some(vec)
-            })
-            .flatten();
-
-        let projects = projects
-            .iter()
-            .flatten()
-            .flat_map(|edge| &edge.node)
-            .filter_map(|node| match Id::try_from(node.id.as_str()) {
-                Ok(Id(id)) => Some(Project {
+                }
+
+                projects.push(Project {
                     id,
                     name: node.name.clone(),
-                }),
-                _ => None,
-            })
-            .collect();
+                    full_path: node.full_path.clone(),
+                    web_url: node.web_url.clone(),
+                    group_id: node
+                        .group
+                        .as_ref()
+                        .and_then(|group| Id::try_from(group.id.as_str()).ok())
+                        .map(|Id(id)| id),
+                });
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
 
+        let root_group = root_group
+            .ok_or_else(|| FetchError::FetchError("Group response is empty".to_string()))?;
+
+        let group_milestones: Vec<Milestone> = self.fetch(client, &root_group)?;
+        let milestones = group_milestones.into_iter().chain(milestones).collect();
+
+        Ok((root_group, projects, milestones))
+    }
+}
+
+/// Fetches the milestones owned by a group, without those of its subgroups
+impl Fetch<Vec<Milestone>, &Group> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        group: &Group,
+    ) -> anyhow::Result<Vec<Milestone>> {
+        use group_milestones_query as q;
+
+        let mut milestones = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupMilestonesQuery::build_query(q::Variables {
+                full_path: group.full_path.clone(),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData =
+                self.post(client, &query, &group.full_path, cursor.as_deref())?;
+            let group_data = data.group.ok_or_else(|| {
+                FetchError::from("No group data on group milestones query response")
+            })?;
+            let Some(group_milestones) = group_data.milestones else {
+                break;
+            };
+
+            let has_next_page = group_milestones.page_info.has_next_page;
+            let end_cursor = group_milestones.page_info.end_cursor.clone();
+
+            milestones.extend(
+                group_milestones
+                    .edges
+                    .unwrap_or_default()
+                    .iter()
+                    .flatten()
+                    .flat_map(|edge| edge.node.as_ref())
+                    .filter_map(|node| milestone!(node, self, Some(group.id), None)),
+            );
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(milestones)
+    }
+}
+
+impl Fetcher {
+    /// Fetches the remaining milestones of a project, starting after `cursor`
+    fn fetch_project_milestones(
+        &self,
+        client: &reqwest::blocking::Client,
+        project_gid: String,
+        project_id: u32,
+        mut cursor: Option<String>,
+    ) -> anyhow::Result<Vec<Milestone>> {
+        use project_milestones_query as q;
+
+        let mut milestones = vec![];
+
+        loop {
+            let query = ProjectMilestonesQuery::build_query(q::Variables {
+                project_ids: Some(vec![project_gid.clone()]),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData =
+                self.post(client, &query, &project_gid, cursor.as_deref())?;
+            let project = data
+                .projects
+                .and_then(|projects| projects.nodes)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .next()
+                .ok_or_else(|| {
+                    FetchError::from("No project data on project milestones query response")
+                })?;
+            let Some(project_milestones) = project.milestones else {
+                break;
+            };
+
+            let has_next_page = project_milestones.page_info.has_next_page;
+            let end_cursor = project_milestones.page_info.end_cursor.clone();
+
+            milestones.extend(
+                project_milestones
+                    .nodes
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    .filter_map(|node| milestone!(node, self, None, Some(project_id))),
+            );
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(milestones)
+    }
+}
+
+/// Fetches all descendant groups (subgroups, sub-subgroups, ...) of a group
+impl Fetch<Vec<Group>, String> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        full_path: String,
+    ) -> anyhow::Result<Vec<Group>> {
+        use subgroups_query as q;
+
+        let mut groups = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = SubgroupsQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
+            let group = data
+                .group
+                .ok_or_else(|| FetchError::from("No group data on subgroups query response"))?;
+            let descendant_groups = group.descendant_groups.ok_or_else(|| {
+                FetchError::from(
+                    "No descendantGroups data on subgroups query response's group object",
+                )
+            })?;
+
+            let has_next_page = descendant_groups.page_info.has_next_page;
+            let end_cursor = descendant_groups.page_info.end_cursor.clone();
+
+            let nodes = descendant_groups.nodes.ok_or_else(|| {
+                FetchError::from(
+                    "No nodes data on subgroups query response's group.descendantGroups object",
+                )
+            })?;
+
+            for node in nodes.iter().flatten() {
+                let Id(id) = Id::try_from(node.id.as_str())?;
+                let parent_id = match node.parent.as_ref() {
+                    Some(parent) => {
+                        let Id(id) = Id::try_from(parent.id.as_str())?;
+                        Some(id)
+                    }
+                    None => None,
+                };
+                groups.push(Group {
+                    id,
+                    name: node.name.clone(),
+                    full_path: node.full_path.clone(),
+                    parent_id,
+                });
+            }
 
-        let milestones = group_milestones
-            .into_iter()
-            .chain(project_milestones.into_iter())
-            .collect();
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
 
-        Ok((projects, milestones))
+        Ok(groups)
     }
 }
@@ -1,13 +1,17 @@
-use graphql_client::{GraphQLQuery, Response};
+use std::collections::HashMap;
+
+use graphql_client::GraphQLQuery;
 
 use super::{Fetch, Fetcher};
 use crate::{
     graphql::Id,
-    model::{Issue, TimeLog},
+    model::{Issue, IssueLink, Project, TimeLog},
     FetchError,
 };
 
 type Time = String;
+type TimelogID = String;
+type NoteID = String;
 
 // The paths are relative to the directory where your `Cargo.toml` is located.
 // Both json and the GraphQL schema language are supported as sources for the schema
@@ -22,11 +26,44 @@ type Time = String;
 )]
 pub(crate) struct IssuesQuery;
 
-impl Fetch<(Vec<Issue>, Vec<TimeLog>), String> for Fetcher {
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./issue_timelogs_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct IssueTimelogsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./issues_planning_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct IssuesPlanningQuery;
+
+/// Weight, epic and iteration of an issue, which are only available on GitLab Premium
+#[derive(Debug, Default)]
+pub(crate) struct IssuePlanning {
+    pub(crate) weight: Option<i64>,
+    pub(crate) epic_id: Option<u32>,
+    pub(crate) iteration_id: Option<u32>,
+}
+
+/// Fetches all issues of a project together with their time logs.
+///
+/// If `updated_after` is set, only issues updated after that time are fetched.
+impl Fetch<(Vec<Issue>, Vec<TimeLog>), (String, Option<String>)> for Fetcher {
     fn fetch(
         &self,
         client: &reqwest::blocking::Client,
-        full_path: String,
+        (full_path, updated_after): (String, Option<String>),
     ) -> anyhow::Result<(Vec<Issue>, Vec<TimeLog>)> {
         use issues_query as q;
 
@@ -38,12 +75,10 @@ impl Fetch<(Vec<Issue>, Vec<TimeLog>), String> for Fetcher {
             let query = IssuesQuery::build_query(q::Variables {
                 full_path: full_path.clone(),
                 cursor: cursor.clone(),
+                updated_after: updated_after.clone(),
+                types: query_issue_types(&self.options.issue_types),
             });
-            let resp = client.post(&self.base_uri).json(&query).send()?;
-            let response_body: Response<q::ResponseData> = resp.json()?;
-            let data: q::ResponseData = response_body
-                .data
-                .ok_or_else(|| FetchError::from("No data on issues query response"))?;
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
             let project = data
                 .project
                 .ok_or_else(|| FetchError::from("No project data on issues query response"))?;
@@ -60,23 +95,7 @@ impl Fetch<(Vec<Issue>, Vec<TimeLog>), String> for Fetcher {
             })?;
 
             for issue in issues_vec.iter().flatten() {
-                let Id(id) = Id::try_from(issue.id.as_str())?;
-                let iid = issue.iid.parse()?;
-                let milestone_id = match issue.milestone.as_ref() {
-                    Some(ms) => {
-                        let Id(id) = Id::try_from(ms.id.as_str())?;
-                        Some(id)
-                    }
-                    None => None,
-                };
-                issues.push(Issue {
-                    id,
-                    iid,
-                    project_id,
-                    name: issue.title.clone(),
-                    milestone_id,
-                });
-
+                issues.push(issue!(issue, project_id, issues_query));
                 let issue_id = issues.last().map(|i| i.id);
 
                 for time_log in issue
@@ -87,21 +106,17 @@ impl Fetch<(Vec<Issue>, Vec<TimeLog>), String> for Fetcher {
                     .iter()
                     .flatten()
                 {
-                    let Id(user_id) = Id::try_from(time_log.user.id.as_str())?;
-                    let date = time_log
-                        .spent_at
-                        .as_ref()
-                        .ok_or_else(|| FetchError::from("No date at time log"))?
-                        .clone();
-                    time_logs.push({
-                        TimeLog {
-                            time: time_log.time_spent as i32 / 60,
-                            user_id,
-                            date,
-                            issue_id,
-                            merge_request_id: None,
-                        }
-                    });
+                    time_logs.push(time_log!(time_log, issue_id, None));
+                }
+
+                if issue.timelogs.page_info.has_next_page {
+                    time_logs.extend(self.fetch_issue_time_logs(
+                        client,
+                        full_path.clone(),
+                        issue.iid.clone(),
+                        issue_id,
+                        issue.timelogs.page_info.end_cursor.clone(),
+                    )?);
                 }
             }
 
@@ -115,3 +130,168 @@ impl Fetch<(Vec<Issue>, Vec<TimeLog>), String> for Fetcher {
         Ok((issues, time_logs))
     }
 }
+
+impl Fetcher {
+    /// Fetches the remaining time logs of an issue, starting after `cursor`
+    fn fetch_issue_time_logs(
+        &self,
+        client: &reqwest::blocking::Client,
+        full_path: String,
+        iid: String,
+        issue_id: Option<u32>,
+        mut cursor: Option<String>,
+    ) -> anyhow::Result<Vec<TimeLog>> {
+        use issue_timelogs_query as q;
+
+        let mut time_logs = vec![];
+
+        loop {
+            let query = IssueTimelogsQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                iid: Some(iid.clone()),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(
+                client,
+                &query,
+                &format!("{}#{}", full_path, iid),
+                cursor.as_deref(),
+            )?;
+            let issue = data
+                .project
+                .and_then(|project| project.issue)
+                .ok_or_else(|| {
+                    FetchError::from("No issue data on issue timelogs query response")
+                })?;
+
+            let has_next_page = issue.timelogs.page_info.has_next_page;
+            let end_cursor = issue.timelogs.page_info.end_cursor.clone();
+
+            for time_log in issue
+                .timelogs
+                .nodes
+                .as_ref()
+                .ok_or_else(|| FetchError::from("No 'nodes' in timeLog found"))?
+                .iter()
+                .flatten()
+            {
+                time_logs.push(time_log!(time_log, issue_id, None));
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(time_logs)
+    }
+}
+
+/// Fetches weight, epic and iteration of the issues of a project with the given iids, keyed by the
+/// issue's id.
+impl Fetch<HashMap<u32, IssuePlanning>, (&Project, &[String])> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        (project, iids): (&Project, &[String]),
+    ) -> anyhow::Result<HashMap<u32, IssuePlanning>> {
+        use issues_planning_query as q;
+
+        let mut planning = HashMap::new();
+
+        // Keep the requests small for projects with many issues
+        for iids in iids.chunks(100) {
+            let mut cursor: Option<String> = None;
+            loop {
+                let query = IssuesPlanningQuery::build_query(q::Variables {
+                    full_path: project.full_path.clone(),
+                    iids: Some(iids.to_vec()),
+                    cursor: cursor.clone(),
+                });
+                let data: q::ResponseData =
+                    self.post(client, &query, &project.full_path, cursor.as_deref())?;
+                let issues = data
+                    .project
+                    .and_then(|project| project.issues)
+                    .ok_or_else(|| {
+                        FetchError::from("No issues data on issues planning query response")
+                    })?;
+
+                let has_next_page = issues.page_info.has_next_page;
+                let end_cursor = issues.page_info.end_cursor.clone();
+
+                for issue in issues.nodes.iter().flatten().flatten() {
+                    let Id(id) = Id::try_from(issue.id.as_str())?;
+                    let epic_id = match issue.epic.as_ref() {
+                        Some(epic) => {
+                            let Id(id) = Id::try_from(epic.id.as_str())?;
+                            Some(id)
+                        }
+                        None => None,
+                    };
+                    let iteration_id = match issue.iteration.as_ref() {
+                        Some(iteration) => {
+                            let Id(id) = Id::try_from(iteration.id.as_str())?;
+                            Some(id)
+                        }
+                        None => None,
+                    };
+                    planning.insert(
+                        id,
+                        IssuePlanning {
+                            weight: issue.weight,
+                            epic_id,
+                            iteration_id,
+                        },
+                    );
+                }
+
+                if has_next_page {
+                    cursor = end_cursor.clone();
+                } else {
+                    break;
+                };
+            }
+        }
+
+        Ok(planning)
+    }
+}
+
+/// Fetches the links of an issue to other issues ("relates to", "blocks" and "is blocked by").
+///
+/// The GraphQL API only exposes blocking issues, so the links are taken from the REST API.
+impl Fetch<Vec<IssueLink>, &Issue> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        issue: &Issue,
+    ) -> anyhow::Result<Vec<IssueLink>> {
+        self.get_all(
+            client,
+            &format!("projects/{}/issues/{}/links", issue.project_id, issue.iid),
+        )
+    }
+}
+
+/// Turns the issue types to filter by into query variables, `None` fetching all types
+fn query_issue_types(issue_types: &[crate::IssueType]) -> Option<Vec<issues_query::IssueType>> {
+    use issues_query::IssueType;
+
+    if issue_types.is_empty() {
+        return None;
+    }
+    let types = issue_types
+        .iter()
+        .map(|issue_type| match issue_type {
+            crate::IssueType::Issue => IssueType::ISSUE,
+            crate::IssueType::Incident => IssueType::INCIDENT,
+            crate::IssueType::TestCase => IssueType::TEST_CASE,
+            crate::IssueType::Requirement => IssueType::REQUIREMENT,
+            crate::IssueType::Task => IssueType::TASK,
+        })
+        .collect();
+    Some(types)
+}
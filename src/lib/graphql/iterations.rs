@@ -0,0 +1,164 @@
+use graphql_client::GraphQLQuery;
+
+use super::{Fetch, Fetcher};
+use crate::{
+    graphql::Id,
+    model::{Iteration, IterationCadence},
+    FetchError,
+};
+
+type Time = String;
+type IterationsCadenceID = String;
+
+// The paths are relative to the directory where your `Cargo.toml` is located.
+// Both json and the GraphQL schema language are supported as sources for the schema
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./group_iteration_cadences_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct GroupIterationCadencesQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./group_iterations_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct GroupIterationsQuery;
+
+/// Fetches the iteration cadences of a group.
+///
+/// If `include_ancestors` is set, the cadences of all ancestor groups are included.
+impl Fetch<Vec<IterationCadence>, (String, bool)> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        (full_path, include_ancestors): (String, bool),
+    ) -> anyhow::Result<Vec<IterationCadence>> {
+        use group_iteration_cadences_query as q;
+
+        let mut cadences = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupIterationCadencesQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                include_ancestors: Some(include_ancestors),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
+            let group = data.group.ok_or_else(|| {
+                FetchError::from("No group data on group iteration cadences query response")
+            })?;
+            // Iterations are not available on every GitLab tier
+            let Some(group_cadences) = group.iteration_cadences else {
+                break;
+            };
+
+            let has_next_page = group_cadences.page_info.has_next_page;
+            let end_cursor = group_cadences.page_info.end_cursor.clone();
+
+            for cadence in group_cadences.nodes.iter().flatten().flatten() {
+                let Id(id) = Id::try_from(cadence.id.as_str())?;
+                cadences.push(IterationCadence {
+                    id,
+                    name: cadence.title.clone(),
+                    description: cadence.description.clone(),
+                    duration_in_weeks: cadence.duration_in_weeks,
+                    automatic: cadence.automatic,
+                    active: cadence.active,
+                    start_date: cadence.start_date.clone(),
+                });
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(cadences)
+    }
+}
+
+/// Fetches the iterations of a group.
+///
+/// If `include_ancestors` is set, the iterations of all ancestor groups are included.
+impl Fetch<Vec<Iteration>, (String, bool)> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        (full_path, include_ancestors): (String, bool),
+    ) -> anyhow::Result<Vec<Iteration>> {
+        use group_iterations_query as q;
+
+        let mut iterations = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupIterationsQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                include_ancestors: Some(include_ancestors),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
+            let group = data.group.ok_or_else(|| {
+                FetchError::from("No group data on group iterations query response")
+            })?;
+            let Some(group_iterations) = group.iterations else {
+                break;
+            };
+
+            let has_next_page = group_iterations.page_info.has_next_page;
+            let end_cursor = group_iterations.page_info.end_cursor.clone();
+
+            for iteration in group_iterations.nodes.iter().flatten().flatten() {
+                let Id(id) = Id::try_from(iteration.id.as_str())?;
+                let Id(cadence_id) = Id::try_from(iteration.iteration_cadence.id.as_str())?;
+                iterations.push(Iteration {
+                    id,
+                    iid: iteration.iid.parse()?,
+                    name: iteration.title.clone(),
+                    description: iteration.description.clone(),
+                    state: iteration_state(&iteration.state),
+                    start_date: iteration.start_date.clone(),
+                    due_date: iteration.due_date.clone(),
+                    web_url: iteration.web_url.clone(),
+                    cadence_id,
+                });
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(iterations)
+    }
+}
+
+fn iteration_state(state: &group_iterations_query::IterationState) -> String {
+    use group_iterations_query::IterationState;
+
+    match state {
+        IterationState::upcoming => "upcoming",
+        IterationState::started => "started",
+        IterationState::current => "current",
+        IterationState::opened => "opened",
+        IterationState::closed => "closed",
+        IterationState::all => "all",
+        IterationState::Other(state) => state,
+    }
+    .to_string()
+}
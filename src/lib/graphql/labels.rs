@@ -0,0 +1,70 @@
+use graphql_client::GraphQLQuery;
+
+use super::{Fetch, Fetcher};
+use crate::{graphql::Id, model::Label, FetchError};
+
+// The paths are relative to the directory where your `Cargo.toml` is located.
+// Both json and the GraphQL schema language are supported as sources for the schema
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./group_labels_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct GroupLabelsQuery;
+
+/// Fetches the labels available in a group: its own, those of its ancestor groups and those of its
+/// projects.
+///
+/// If `include_subgroups` is set, the labels of all subgroups and their projects are included.
+impl Fetch<Vec<Label>, (String, bool)> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        (full_path, include_subgroups): (String, bool),
+    ) -> anyhow::Result<Vec<Label>> {
+        use group_labels_query as q;
+
+        let mut labels = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupLabelsQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                include_subgroups: Some(include_subgroups),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
+            let group = data
+                .group
+                .ok_or_else(|| FetchError::from("No group data on group labels query response"))?;
+            let group_labels = group
+                .labels
+                .ok_or_else(|| FetchError::from("No labels data on group labels query response"))?;
+
+            let has_next_page = group_labels.page_info.has_next_page;
+            let end_cursor = group_labels.page_info.end_cursor.clone();
+
+            for label in group_labels.nodes.iter().flatten().flatten() {
+                let Id(id) = Id::try_from(label.id.as_str())?;
+                labels.push(Label {
+                    id,
+                    title: label.title.clone(),
+                    color: label.color.clone(),
+                    description: label.description.clone(),
+                });
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(labels)
+    }
+}
@@ -0,0 +1,206 @@
+//! Mapping of the nodes shared by several queries to the model.
+//!
+//! graphql_client generates distinct types for the same node in every query, so the mappings are
+//! macros that work on any of them. Unless noted otherwise, they use `?` and must be called from a
+//! function returning an `anyhow::Result`.
+
+use super::{Id, IdError};
+
+/// Parses the id of an optional node, like the milestone of an issue
+pub(crate) fn optional_id(id: Option<&str>) -> Result<Option<u32>, IdError> {
+    id.map(|id| Id::try_from(id).map(|Id(id)| id)).transpose()
+}
+
+/// Parses the ids of a connection of users, like the assignees of an issue
+macro_rules! user_ids {
+    ($users:expr) => {{
+        let mut ids = vec![];
+        for user in $users
+            .as_ref()
+            .and_then(|users| users.nodes.as_ref())
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let $crate::graphql::Id(id) = $crate::graphql::Id::try_from(user.id.as_str())?;
+            ids.push(id);
+        }
+        ids
+    }};
+}
+
+/// Maps a connection of labels
+macro_rules! labels {
+    ($labels:expr) => {{
+        let mut labels = vec![];
+        for label in $labels
+            .as_ref()
+            .and_then(|labels| labels.nodes.as_ref())
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let $crate::graphql::Id(id) = $crate::graphql::Id::try_from(label.id.as_str())?;
+            labels.push($crate::model::Label {
+                id,
+                title: label.title.clone(),
+                color: label.color.clone(),
+                description: label.description.clone(),
+            });
+        }
+        labels
+    }};
+}
+
+/// Maps a time log spent on the issue `issue_id` or the merge request `merge_request_id`
+macro_rules! time_log {
+    ($time_log:expr, $issue_id:expr, $merge_request_id:expr) => {{
+        let time_log = $time_log;
+        let $crate::graphql::Id(id) = $crate::graphql::Id::try_from(time_log.id.as_str())?;
+        let $crate::graphql::Id(user_id) =
+            $crate::graphql::Id::try_from(time_log.user.id.as_str())?;
+        let date = time_log
+            .spent_at
+            .as_ref()
+            .ok_or_else(|| $crate::FetchError::from("No date at time log"))?
+            .clone();
+        $crate::model::TimeLog {
+            id,
+            seconds: time_log.time_spent,
+            summary: time_log.summary.clone(),
+            note_id: $crate::graphql::mapping::optional_id(
+                time_log.note.as_ref().map(|note| note.id.as_str()),
+            )?,
+            note_created_at: time_log.note.as_ref().map(|note| note.created_at.clone()),
+            user_id,
+            date,
+            issue_id: $issue_id,
+            merge_request_id: $merge_request_id,
+        }
+    }};
+}
+
+/// Maps an issue of the project `project_id`, `query` being the module generated for its query
+macro_rules! issue {
+    ($issue:expr, $project_id:expr, $query:ident) => {{
+        use $query::{IssueState, IssueType};
+
+        let issue = $issue;
+        let $crate::graphql::Id(id) = $crate::graphql::Id::try_from(issue.id.as_str())?;
+        let $crate::graphql::Id(author_id) =
+            $crate::graphql::Id::try_from(issue.author.id.as_str())?;
+        let issue_type = match issue.type_.as_ref() {
+            // Issues without type predate the other types
+            None | Some(IssueType::ISSUE) => "issue",
+            Some(IssueType::INCIDENT) => "incident",
+            Some(IssueType::TEST_CASE) => "test_case",
+            Some(IssueType::REQUIREMENT) => "requirement",
+            Some(IssueType::TASK) => "task",
+            Some(IssueType::Other(issue_type)) => issue_type,
+        }
+        .to_lowercase();
+        let state = match &issue.state {
+            IssueState::opened => "opened",
+            IssueState::closed => "closed",
+            IssueState::locked => "locked",
+            IssueState::all => "all",
+            IssueState::Other(state) => state,
+        }
+        .to_string();
+        $crate::model::Issue {
+            id,
+            iid: issue.iid.parse()?,
+            project_id: $project_id,
+            name: issue.title.clone(),
+            milestone_id: $crate::graphql::mapping::optional_id(
+                issue.milestone.as_ref().map(|ms| ms.id.as_str()),
+            )?,
+            // Fetched separately for all issues, as not every GitLab edition has them
+            epic_id: None,
+            iteration_id: None,
+            issue_type,
+            state,
+            created_at: issue.created_at.clone(),
+            closed_at: issue.closed_at.clone(),
+            time_estimate: issue.time_estimate,
+            total_time_spent: issue.total_time_spent,
+            // Fetched separately along with the epic and iteration
+            weight: None,
+            due_date: issue.due_date.clone(),
+            web_url: issue.web_url.clone(),
+            author_id,
+            assignee_ids: user_ids!(issue.assignees),
+            labels: labels!(issue.labels),
+            // Fetched separately for all issues
+            parent_id: None,
+            links: None,
+        }
+    }};
+}
+
+/// Maps a merge request of the project `project_id`, `query` being the module generated for its
+/// query
+macro_rules! merge_request {
+    ($merge_request:expr, $project_id:expr, $query:ident) => {{
+        use $query::MergeRequestState;
+
+        let merge_request = $merge_request;
+        let $crate::graphql::Id(id) = $crate::graphql::Id::try_from(merge_request.id.as_str())?;
+        let state = match &merge_request.state {
+            MergeRequestState::opened => "opened",
+            MergeRequestState::closed => "closed",
+            MergeRequestState::locked => "locked",
+            MergeRequestState::merged => "merged",
+            MergeRequestState::all => "all",
+            MergeRequestState::Other(state) => state,
+        }
+        .to_string();
+        $crate::model::MergeRequest {
+            id,
+            iid: merge_request.iid.parse()?,
+            project_id: $project_id,
+            name: merge_request.title.clone(),
+            milestone_id: $crate::graphql::mapping::optional_id(
+                merge_request.milestone.as_ref().map(|ms| ms.id.as_str()),
+            )?,
+            state,
+            merged_at: merge_request.merged_at.clone(),
+            source_branch: merge_request.source_branch.clone(),
+            target_branch: merge_request.target_branch.clone(),
+            web_url: merge_request.web_url.clone(),
+            author_id: $crate::graphql::mapping::optional_id(
+                merge_request
+                    .author
+                    .as_ref()
+                    .map(|author| author.id.as_str()),
+            )?,
+            assignee_ids: user_ids!(merge_request.assignees),
+            reviewer_ids: user_ids!(merge_request.reviewers),
+            // Fetched separately from the REST API
+            issue_ids: None,
+            labels: labels!(merge_request.labels),
+        }
+    }};
+}
+
+/// Maps a milestone owned by the group `group_id` or the project `project_id`, using `fetcher` to
+/// resolve its URL. Evaluates to `None` for a milestone without a valid id, without using `?`.
+macro_rules! milestone {
+    ($milestone:expr, $fetcher:expr, $group_id:expr, $project_id:expr) => {{
+        let milestone = $milestone;
+        match $crate::graphql::Id::try_from(milestone.id.as_str()) {
+            Ok($crate::graphql::Id(id)) => Some($crate::model::Milestone {
+                id,
+                name: milestone.title.clone(),
+                description: milestone.description.clone(),
+                state: milestone.state,
+                start_date: milestone.start_date.clone(),
+                due_date: milestone.due_date.clone(),
+                web_url: $fetcher.web_url(&milestone.web_path),
+                group_id: $group_id,
+                project_id: $project_id,
+            }),
+            Err(_) => None,
+        }
+    }};
+}
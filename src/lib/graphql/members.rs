@@ -0,0 +1,160 @@
+use graphql_client::GraphQLQuery;
+
+use super::{Fetch, Fetcher};
+use crate::{
+    graphql::Id,
+    model::{AccessLevel as AccessLevelEnum, Group, Membership, Project},
+    FetchError,
+};
+
+type Time = String;
+
+// The paths are relative to the directory where your `Cargo.toml` is located.
+// Both json and the GraphQL schema language are supported as sources for the schema
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./group_members_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    extern_enums("AccessLevelEnum"),
+    skip_serializing_none
+)]
+pub(crate) struct GroupMembersQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./project_members_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    extern_enums("AccessLevelEnum"),
+    skip_serializing_none
+)]
+pub(crate) struct ProjectMembersQuery;
+
+/// Fetches the members of a group, including those inherited from its ancestor groups
+impl Fetch<Vec<Membership>, &Group> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        group: &Group,
+    ) -> anyhow::Result<Vec<Membership>> {
+        use group_members_query as q;
+
+        let mut memberships = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupMembersQuery::build_query(q::Variables {
+                full_path: group.full_path.clone(),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData =
+                self.post(client, &query, &group.full_path, cursor.as_deref())?;
+            let group_data = data
+                .group
+                .ok_or_else(|| FetchError::from("No group data on group members query response"))?;
+            let members = group_data.group_members.ok_or_else(|| {
+                FetchError::from("No members data on group members query response")
+            })?;
+
+            let has_next_page = members.page_info.has_next_page;
+            let end_cursor = members.page_info.end_cursor.clone();
+
+            for member in members.nodes.iter().flatten().flatten() {
+                // Invitations that have not been accepted yet have no user
+                let Some(user) = &member.user else {
+                    continue;
+                };
+                let Id(user_id) = Id::try_from(user.id.as_str())?;
+                let source_group_id = match member.group.as_ref() {
+                    Some(source) => {
+                        let Id(id) = Id::try_from(source.id.as_str())?;
+                        Some(id)
+                    }
+                    None => None,
+                };
+                let access_level = member.access_level.as_ref();
+                memberships.push(Membership {
+                    user_id,
+                    group_id: Some(group.id),
+                    project_id: None,
+                    access_level: access_level.and_then(|level| level.integer_value),
+                    access_level_name: access_level.and_then(|level| level.string_value),
+                    inherited: source_group_id.is_some_and(|id| id != group.id),
+                    created_at: member.created_at.clone(),
+                    expires_at: member.expires_at.clone(),
+                });
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(memberships)
+    }
+}
+
+/// Fetches the direct members of a project.
+///
+/// Members inherited from the project's groups are part of the groups' members.
+impl Fetch<Vec<Membership>, &Project> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        project: &Project,
+    ) -> anyhow::Result<Vec<Membership>> {
+        use project_members_query as q;
+
+        let mut memberships = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = ProjectMembersQuery::build_query(q::Variables {
+                full_path: project.full_path.clone(),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData =
+                self.post(client, &query, &project.full_path, cursor.as_deref())?;
+            let project_data = data.project.ok_or_else(|| {
+                FetchError::from("No project data on project members query response")
+            })?;
+            let members = project_data.project_members.ok_or_else(|| {
+                FetchError::from("No members data on project members query response")
+            })?;
+
+            let has_next_page = members.page_info.has_next_page;
+            let end_cursor = members.page_info.end_cursor.clone();
+
+            for member in members.nodes.iter().flatten().flatten() {
+                let Some(user) = &member.user else {
+                    continue;
+                };
+                let Id(user_id) = Id::try_from(user.id.as_str())?;
+                let access_level = member.access_level.as_ref();
+                memberships.push(Membership {
+                    user_id,
+                    group_id: None,
+                    project_id: Some(project.id),
+                    access_level: access_level.and_then(|level| level.integer_value),
+                    access_level_name: access_level.and_then(|level| level.string_value),
+                    inherited: false,
+                    created_at: member.created_at.clone(),
+                    expires_at: member.expires_at.clone(),
+                });
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(memberships)
+    }
+}
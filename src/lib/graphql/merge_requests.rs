@@ -1,13 +1,16 @@
-use graphql_client::{GraphQLQuery, Response};
+use graphql_client::GraphQLQuery;
+use serde::Deserialize;
 
 use super::{Fetch, Fetcher};
 use crate::{
     graphql::Id,
-    model::{MergeRequest, TimeLog},
+    model::{MergeRequest, MergeRequestIssueIds, TimeLog},
     FetchError,
 };
 
 type Time = String;
+type TimelogID = String;
+type NoteID = String;
 
 // The paths are relative to the directory where your `Cargo.toml` is located.
 // Both json and the GraphQL schema language are supported as sources for the schema
@@ -22,11 +25,25 @@ type Time = String;
 )]
 pub(crate) struct MergeRequestsQuery;
 
-impl Fetch<(Vec<MergeRequest>, Vec<TimeLog>), String> for Fetcher {
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./merge_request_timelogs_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct MergeRequestTimelogsQuery;
+
+/// Fetches all merge requests of a project together with their time logs.
+///
+/// If `updated_after` is set, only merge requests updated after that time are fetched.
+impl Fetch<(Vec<MergeRequest>, Vec<TimeLog>), (String, Option<String>)> for Fetcher {
     fn fetch(
         &self,
         client: &reqwest::blocking::Client,
-        full_path: String,
+        (full_path, updated_after): (String, Option<String>),
     ) -> anyhow::Result<(Vec<MergeRequest>, Vec<TimeLog>)> {
         use merge_requests_query as q;
 
@@ -38,12 +55,9 @@ impl Fetch<(Vec<MergeRequest>, Vec<TimeLog>), String> for Fetcher {
             let query = MergeRequestsQuery::build_query(q::Variables {
                 full_path: full_path.clone(),
                 cursor: cursor.clone(),
+                updated_after: updated_after.clone(),
             });
-            let resp = client.post(&self.base_uri).json(&query).send()?;
-            let response_body: Response<q::ResponseData> = resp.json()?;
-            let data: q::ResponseData = response_body.data.ok_or_else(|| {
-                FetchError::FetchError("No data on group query response".to_string())
-            })?;
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
             let project = data
                 .project
                 .ok_or_else(|| FetchError::from("No project data in response"))?;
@@ -65,24 +79,11 @@ impl Fetch<(Vec<MergeRequest>, Vec<TimeLog>), String> for Fetcher {
             })?;
 
             for merge_request in merge_request_vec.iter().flatten() {
-                let Id(id) = Id::try_from(merge_request.id.as_str())?;
-                let iid = merge_request.iid.parse()?;
-                let milestone_id = match merge_request.milestone.as_ref() {
-                    Some(ms) => {
-                        let Id(id) = Id::try_from(ms.id.as_str())?;
-                        Some(id)
-                    }
-                    None => None,
-                };
-                merge_requests.push({
-                    MergeRequest {
-                        id,
-                        iid,
-                        project_id,
-                        name: merge_request.title.clone(),
-                        milestone_id,
-                    }
-                });
+                merge_requests.push(merge_request!(
+                    merge_request,
+                    project_id,
+                    merge_requests_query
+                ));
 
                 let merge_request_id = merge_requests.last().map(|mr| mr.id);
 
@@ -96,23 +97,17 @@ impl Fetch<(Vec<MergeRequest>, Vec<TimeLog>), String> for Fetcher {
                     .iter()
                     .flatten()
                 {
-                    time_logs.push({
-                        let Id(user_id) = Id::try_from(time_log.user.id.as_str())?;
-                        let date = time_log
-                            .spent_at
-                            .as_ref()
-                            .ok_or_else(|| {
-                                FetchError::FetchError("No date at time log".to_string())
-                            })?
-                            .clone();
-                        TimeLog {
-                            time: time_log.time_spent as i32 / 60,
-                            user_id,
-                            date,
-                            issue_id: None,
-                            merge_request_id,
-                        }
-                    });
+                    time_logs.push(time_log!(time_log, None, merge_request_id));
+                }
+
+                if merge_request.timelogs.page_info.has_next_page {
+                    time_logs.extend(self.fetch_merge_request_time_logs(
+                        client,
+                        full_path.clone(),
+                        merge_request.iid.clone(),
+                        merge_request_id,
+                        merge_request.timelogs.page_info.end_cursor.clone(),
+                    )?);
                 }
             }
 
@@ -126,3 +121,135 @@ impl Fetch<(Vec<MergeRequest>, Vec<TimeLog>), String> for Fetcher {
         Ok((merge_requests, time_logs))
     }
 }
+
+impl Fetcher {
+    /// Fetches the remaining time logs of a merge request, starting after `cursor`
+    fn fetch_merge_request_time_logs(
+        &self,
+        client: &reqwest::blocking::Client,
+        full_path: String,
+        iid: String,
+        merge_request_id: Option<u32>,
+        mut cursor: Option<String>,
+    ) -> anyhow::Result<Vec<TimeLog>> {
+        use merge_request_timelogs_query as q;
+
+        let mut time_logs = vec![];
+
+        loop {
+            let query = MergeRequestTimelogsQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                iid: iid.clone(),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(
+                client,
+                &query,
+                &format!("{}!{}", full_path, iid),
+                cursor.as_deref(),
+            )?;
+            let merge_request = data
+                .project
+                .and_then(|project| project.merge_request)
+                .ok_or_else(|| {
+                    FetchError::from(
+                        "No merge request data on merge request timelogs query response",
+                    )
+                })?;
+
+            let has_next_page = merge_request.timelogs.page_info.has_next_page;
+            let end_cursor = merge_request.timelogs.page_info.end_cursor.clone();
+
+            for time_log in merge_request
+                .timelogs
+                .nodes
+                .as_ref()
+                .ok_or_else(|| FetchError::from("No 'nodes' in timeLog found"))?
+                .iter()
+                .flatten()
+            {
+                time_logs.push(time_log!(time_log, None, merge_request_id));
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(time_logs)
+    }
+}
+
+/// An issue as returned by the REST API, reduced to what is needed to link it
+#[derive(Deserialize)]
+struct IssueReference {
+    id: IssueReferenceId,
+}
+
+/// Issues of external trackers like Jira have ids like `PROJ-123`, which cannot be linked
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IssueReferenceId {
+    GitLab(u32),
+    External(serde::de::IgnoredAny),
+}
+
+impl IssueReference {
+    fn gitlab_id(&self) -> Option<u32> {
+        match self.id {
+            IssueReferenceId::GitLab(id) => Some(id),
+            IssueReferenceId::External(_) => None,
+        }
+    }
+}
+
+/// Fetches the ids of the issues a merge request closes and of the issues it only mentions.
+///
+/// The GraphQL API does not expose these, so they are taken from the REST API.
+impl Fetch<MergeRequestIssueIds, &MergeRequest> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        merge_request: &MergeRequest,
+    ) -> anyhow::Result<MergeRequestIssueIds> {
+        let path = format!(
+            "projects/{}/merge_requests/{}",
+            merge_request.project_id, merge_request.iid
+        );
+        let closes: Vec<IssueReference> =
+            self.get_all(client, &format!("{}/closes_issues", path))?;
+        let related: Vec<IssueReference> =
+            self.get_all(client, &format!("{}/related_issues", path))?;
+
+        let closes: Vec<u32> = closes
+            .iter()
+            .filter_map(IssueReference::gitlab_id)
+            .collect();
+        let related = related
+            .iter()
+            .filter_map(IssueReference::gitlab_id)
+            .filter(|id| !closes.contains(id))
+            .collect();
+
+        Ok(MergeRequestIssueIds { closes, related })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issue_references_keep_only_gitlab_ids() {
+        let issues: Vec<IssueReference> =
+            serde_json::from_str(r#"[{"id": 123}, {"id": "PROJ-1"}, {"id": 456}]"#).unwrap();
+        let ids: Vec<u32> = issues
+            .iter()
+            .filter_map(IssueReference::gitlab_id)
+            .collect();
+
+        assert_eq!(ids, vec![123, 456]);
+    }
+}
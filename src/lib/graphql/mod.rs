@@ -1,17 +1,32 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use rayon::prelude::*;
 use thiserror::Error;
 
 use crate::{
-    model::{Issue, MergeRequest, Milestone, Project, TimeLog, User},
-    FetchError, FetchResult,
+    model::{
+        dedup, Epic, Group, Issue, IssueLink, Iteration, IterationCadence, Label, Membership,
+        MergeRequest, MergeRequestIssueIds, Milestone, Project, TimeLog, User,
+    },
+    FetchError, FetchOptions, FetchResult, FetchStrategy,
 };
 
+// Declared first, so its macros are available in the other modules
+#[macro_use]
+mod mapping;
+
+mod epics;
 mod group;
 mod issues;
+mod iterations;
+mod labels;
+mod members;
 mod merge_requests;
 // mod projects;
+mod request;
+mod timelogs;
 mod users;
+mod work_items;
 
 trait Fetch<T, I> {
     fn fetch(&self, client: &reqwest::blocking::Client, id: I) -> anyhow::Result<T>;
@@ -31,7 +46,7 @@ impl TryFrom<&str> for Id {
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         let part = value
             .split('/')
-            .last()
+            .next_back()
             .ok_or_else(|| IdError::ParseError(value.to_string()))?;
         let id = part
             .parse()
@@ -43,10 +58,17 @@ impl TryFrom<&str> for Id {
 pub(crate) struct Fetcher {
     base_uri: String,
     client: reqwest::blocking::Client,
+    /// Runs the concurrent requests, with up to `jobs` threads
+    pool: rayon::ThreadPool,
+    options: FetchOptions,
 }
 
 impl Fetcher {
-    pub fn new(base_uri: String, personal_access_token: String) -> anyhow::Result<Self> {
+    pub fn new(
+        base_uri: String,
+        personal_access_token: String,
+        options: FetchOptions,
+    ) -> anyhow::Result<Self> {
         let headers = {
             let mut h = reqwest::header::HeaderMap::new();
             h.insert(
@@ -60,48 +82,430 @@ impl Fetcher {
             .build()
             .map_err(|_| FetchError::FetchError("Cannot construct HTTP client".to_string()))?;
 
-        Ok(Fetcher { base_uri, client })
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(options.jobs)
+            .build()?;
+
+        Ok(Fetcher {
+            base_uri,
+            client,
+            pool,
+            options,
+        })
     }
 
-    pub fn go(&self, group_name: String) -> anyhow::Result<FetchResult> {
+    pub fn go(
+        &self,
+        group_name: String,
+        updated_after: Option<String>,
+    ) -> anyhow::Result<FetchResult> {
         let group_name = group_name.replace(' ', "-");
+        let include_subgroups = self.options.include_subgroups;
 
-        let (projects, milestones): (Vec<Project>, Vec<Milestone>) =
-            self.fetch(&self.client, group_name.clone())?;
-        let mut issues: Vec<Issue> = vec![];
-        let mut time_logs: Vec<TimeLog> = vec![];
-        let mut merge_requests: Vec<MergeRequest> = vec![];
+        let (group, mut projects, mut milestones): (Group, Vec<Project>, Vec<Milestone>) =
+            self.fetch(&self.client, (group_name.clone(), include_subgroups))?;
+        let mut groups = vec![group];
+        let subgroups: Vec<Group> = if include_subgroups {
+            self.fetch(&self.client, group_name.clone())?
+        } else {
+            vec![]
+        };
+        for subgroup in &subgroups {
+            let subgroup_milestones: Vec<Milestone> = self.fetch(&self.client, subgroup)?;
+            milestones.extend(subgroup_milestones);
+        }
+        let (mut iteration_cadences, mut iterations) =
+            self.fetch_iterations(&group_name, &subgroups)?;
+        groups.extend(subgroups);
+        dedup(&mut groups);
+        dedup(&mut projects);
+        dedup(&mut milestones);
+        dedup(&mut iteration_cadences);
+        dedup(&mut iterations);
+        let iteration_ids: HashSet<u32> = iterations.iter().map(|it| it.id).collect();
+
+        let mut epics: Vec<Epic> =
+            match self.fetch(&self.client, (group_name.clone(), include_subgroups)) {
+                Ok(epics) => epics,
+                Err(err) if is_unknown_field(&err, "epics") => {
+                    println!("Epics are not supported by this GitLab instance, skipping them");
+                    vec![]
+                }
+                Err(err) => return Err(err),
+            };
+        dedup(&mut epics);
+        // Parents in ancestor groups are not part of the crawl, so they are not referenced
+        let epic_ids: HashSet<u32> = epics.iter().map(|epic| epic.id).collect();
+        for epic in &mut epics {
+            epic.parent_id = epic.parent_id.filter(|id| epic_ids.contains(id));
+        }
 
-        for project in projects.iter() {
-            let full_project_path = format!("{}/{}", group_name, project.name.replace(' ', "-"));
+        let (mut issues, mut merge_requests, mut time_logs) = match self.options.strategy {
+            FetchStrategy::Projects => self.fetch_per_project(&projects, updated_after)?,
+            FetchStrategy::GroupTimelogs => {
+                let (mut issues, mut merge_requests, mut time_logs): (
+                    Vec<Issue>,
+                    Vec<MergeRequest>,
+                    Vec<TimeLog>,
+                ) = self.fetch(
+                    &self.client,
+                    (group_name.clone(), self.options.range.clone()),
+                )?;
 
-            let (new_issues, new_time_logs): (Vec<Issue>, Vec<TimeLog>) =
-                self.fetch(&self.client, full_project_path.clone())?;
-            issues.extend(new_issues);
-            time_logs.extend(new_time_logs);
+                // The group's time logs include all subgroups, even if those are not crawled
+                let project_ids: HashSet<u32> = projects.iter().map(|p| p.id).collect();
+                issues.retain(|issue| project_ids.contains(&issue.project_id));
+                let issue_types = &self.options.issue_types;
+                if !issue_types.is_empty() {
+                    issues.retain(|issue| {
+                        issue_types
+                            .iter()
+                            .any(|issue_type| issue_type.as_str() == issue.issue_type)
+                    });
+                }
+                merge_requests.retain(|mr| project_ids.contains(&mr.project_id));
+                let issue_ids: HashSet<u32> = issues.iter().map(|i| i.id).collect();
+                let merge_request_ids: HashSet<u32> =
+                    merge_requests.iter().map(|mr| mr.id).collect();
+                time_logs.retain(|tl| {
+                    tl.issue_id.is_some_and(|id| issue_ids.contains(&id))
+                        || tl
+                            .merge_request_id
+                            .is_some_and(|id| merge_request_ids.contains(&id))
+                });
 
-            let (new_merge_requests, new_time_logs): (Vec<MergeRequest>, Vec<TimeLog>) =
-                self.fetch(&self.client, full_project_path)?;
-            merge_requests.extend(new_merge_requests);
-            time_logs.extend(new_time_logs);
+                (issues, merge_requests, time_logs)
+            }
+        };
+        dedup(&mut issues);
+        dedup(&mut merge_requests);
+        if self.options.merge_request_issues {
+            self.fetch_merge_request_issues(&mut merge_requests)?;
+        }
+        self.fetch_issue_planning(&projects, &mut issues)?;
+        let parents = self.fetch_work_item_parents(&projects)?;
+        if self.options.issue_links {
+            self.fetch_issue_links(&mut issues)?;
         }
+        for issue in &mut issues {
+            issue.epic_id = issue.epic_id.filter(|id| epic_ids.contains(id));
+            issue.iteration_id = issue.iteration_id.filter(|id| iteration_ids.contains(id));
+            issue.parent_id = parents.get(&issue.id).copied();
+        }
+
+        // Issues and merge requests may carry labels that are no longer part of the catalogue
+        let catalogue: Vec<Label> =
+            self.fetch(&self.client, (group_name.clone(), include_subgroups))?;
+        let mut labels: Vec<Label> = catalogue
+            .into_iter()
+            .chain(issues.iter().flat_map(|issue| issue.labels.iter().cloned()))
+            .chain(
+                merge_requests
+                    .iter()
+                    .flat_map(|mr| mr.labels.iter().cloned()),
+            )
+            .collect();
+        dedup(&mut labels);
+
+        time_logs.retain(|time_log| self.options.range.contains(&time_log.date));
+        dedup(&mut time_logs);
+
+        let mut memberships = self.fetch_memberships(&groups, &projects)?;
+        dedup(&mut memberships);
 
         let user_ids = time_logs
             .iter()
             .map(|tl| tl.user_id)
+            .chain(memberships.iter().map(|membership| membership.user_id))
+            .chain(issues.iter().map(|issue| issue.author_id))
+            .chain(
+                issues
+                    .iter()
+                    .flat_map(|issue| issue.assignee_ids.iter().copied()),
+            )
+            .chain(merge_requests.iter().filter_map(|mr| mr.author_id))
+            .chain(
+                merge_requests
+                    .iter()
+                    .flat_map(|mr| mr.assignee_ids.iter().copied()),
+            )
+            .chain(
+                merge_requests
+                    .iter()
+                    .flat_map(|mr| mr.reviewer_ids.iter().copied()),
+            )
             .collect::<HashSet<u32>>();
 
-        let users: Vec<User> = self.fetch(&self.client, user_ids)?;
+        let mut users: Vec<User> = self.fetch(&self.client, user_ids)?;
+        dedup(&mut users);
 
         // let projects = self.fetch(&self.client, vec![46].as_slice())?;
 
         Ok(FetchResult {
+            groups,
             projects,
             milestones,
+            epics,
+            iteration_cadences,
+            iterations,
             issues,
             merge_requests,
+            labels,
             time_logs,
             users,
+            memberships,
         })
     }
+
+    /// Turns a path relative to the GitLab instance (like `webPath` of milestones) into a URL
+    fn web_url(&self, path: &str) -> Option<String> {
+        let base_uri = reqwest::Url::parse(&self.base_uri).ok()?;
+        base_uri.join(path).ok().map(String::from)
+    }
+
+    /// Turns a path relative to the REST API (like `projects/1/merge_requests`) into a URL.
+    ///
+    /// The REST API lives beside the GraphQL endpoint, at `/api/v4/`.
+    fn rest_url(&self, path: &str) -> anyhow::Result<reqwest::Url> {
+        let base_uri = reqwest::Url::parse(&self.base_uri)?;
+        Ok(base_uri.join(&format!("v4/{}", path))?)
+    }
+
+    /// Fetches issues, merge requests and their time logs project by project.
+    ///
+    /// Up to `jobs` projects are fetched concurrently. The results are merged in the order of
+    /// `projects`.
+    fn fetch_per_project(
+        &self,
+        projects: &[Project],
+        updated_after: Option<String>,
+    ) -> anyhow::Result<(Vec<Issue>, Vec<MergeRequest>, Vec<TimeLog>)> {
+        let results = self.pool.install(|| {
+            projects
+                .par_iter()
+                .map(|project| {
+                    let (issues, issue_time_logs): (Vec<Issue>, Vec<TimeLog>) = self.fetch(
+                        &self.client,
+                        (project.full_path.clone(), updated_after.clone()),
+                    )?;
+                    let (merge_requests, merge_request_time_logs): (
+                        Vec<MergeRequest>,
+                        Vec<TimeLog>,
+                    ) = self.fetch(
+                        &self.client,
+                        (project.full_path.clone(), updated_after.clone()),
+                    )?;
+                    Ok((
+                        issues,
+                        merge_requests,
+                        issue_time_logs
+                            .into_iter()
+                            .chain(merge_request_time_logs)
+                            .collect::<Vec<_>>(),
+                    ))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        })?;
+
+        let mut issues: Vec<Issue> = vec![];
+        let mut time_logs: Vec<TimeLog> = vec![];
+        let mut merge_requests: Vec<MergeRequest> = vec![];
+
+        for (new_issues, new_merge_requests, new_time_logs) in results {
+            issues.extend(new_issues);
+            merge_requests.extend(new_merge_requests);
+            time_logs.extend(new_time_logs);
+        }
+
+        Ok((issues, merge_requests, time_logs))
+    }
+
+    /// Fetches the members of `groups` and the direct members of `projects`.
+    ///
+    /// Up to `jobs` projects are fetched concurrently.
+    fn fetch_memberships(
+        &self,
+        groups: &[Group],
+        projects: &[Project],
+    ) -> anyhow::Result<Vec<Membership>> {
+        let mut memberships = vec![];
+        for group in groups {
+            let group_memberships: Vec<Membership> = self.fetch(&self.client, group)?;
+            memberships.extend(group_memberships);
+        }
+
+        let results = self.pool.install(|| {
+            projects
+                .par_iter()
+                .map(|project| self.fetch(&self.client, project))
+                .collect::<anyhow::Result<Vec<Vec<Membership>>>>()
+        })?;
+        memberships.extend(results.into_iter().flatten());
+
+        Ok(memberships)
+    }
+
+    /// Fetches the iteration cadences and iterations of the group `group_name`, including those of
+    /// its ancestors, and of its `subgroups`.
+    ///
+    /// Iterations are only available on GitLab Premium, so they are skipped on other editions.
+    fn fetch_iterations(
+        &self,
+        group_name: &str,
+        subgroups: &[Group],
+    ) -> anyhow::Result<(Vec<IterationCadence>, Vec<Iteration>)> {
+        let fetch_all = || -> anyhow::Result<(Vec<IterationCadence>, Vec<Iteration>)> {
+            // Projects may use the iterations of all ancestor groups
+            let mut iteration_cadences: Vec<IterationCadence> =
+                self.fetch(&self.client, (group_name.to_string(), true))?;
+            let mut iterations: Vec<Iteration> =
+                self.fetch(&self.client, (group_name.to_string(), true))?;
+            for subgroup in subgroups {
+                let subgroup_cadences: Vec<IterationCadence> =
+                    self.fetch(&self.client, (subgroup.full_path.clone(), false))?;
+                iteration_cadences.extend(subgroup_cadences);
+                let subgroup_iterations: Vec<Iteration> =
+                    self.fetch(&self.client, (subgroup.full_path.clone(), false))?;
+                iterations.extend(subgroup_iterations);
+            }
+            Ok((iteration_cadences, iterations))
+        };
+
+        match fetch_all() {
+            Ok(result) => Ok(result),
+            Err(err)
+                if is_unknown_field(&err, "iterationCadences")
+                    || is_unknown_field(&err, "iterations") =>
+            {
+                println!("Iterations are not supported by this GitLab instance, skipping them");
+                Ok((vec![], vec![]))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Fetches weight, epic and iteration of each of `issues`.
+    ///
+    /// These are only available on GitLab Premium, so they are left empty on other editions. Up to
+    /// `jobs` projects are fetched concurrently.
+    fn fetch_issue_planning(
+        &self,
+        projects: &[Project],
+        issues: &mut [Issue],
+    ) -> anyhow::Result<()> {
+        let results = self.pool.install(|| {
+            projects
+                .par_iter()
+                .map(|project| {
+                    let iids: Vec<String> = issues
+                        .iter()
+                        .filter(|issue| issue.project_id == project.id)
+                        .map(|issue| issue.iid.to_string())
+                        .collect();
+                    if iids.is_empty() {
+                        return Ok(HashMap::new());
+                    }
+                    self.fetch(&self.client, (project, iids.as_slice()))
+                })
+                .collect::<anyhow::Result<Vec<HashMap<u32, issues::IssuePlanning>>>>()
+        });
+
+        let planning: HashMap<u32, issues::IssuePlanning> = match results {
+            Ok(results) => results.into_iter().flatten().collect(),
+            Err(err)
+                if ["weight", "epic", "iteration"]
+                    .iter()
+                    .any(|field| is_unknown_field(&err, field)) =>
+            {
+                println!("Epics, iterations and weights of issues are not supported by this GitLab instance, skipping them");
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        for issue in issues {
+            if let Some(planning) = planning.get(&issue.id) {
+                issue.weight = planning.weight;
+                issue.epic_id = planning.epic_id;
+                issue.iteration_id = planning.iteration_id;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the parents of all work items of `projects`, keyed by the child's id.
+    ///
+    /// GitLab versions without work items have no hierarchy, so it is skipped for them.
+    fn fetch_work_item_parents(&self, projects: &[Project]) -> anyhow::Result<HashMap<u32, u32>> {
+        let results = self.pool.install(|| {
+            projects
+                .par_iter()
+                .map(|project| self.fetch(&self.client, project))
+                .collect::<anyhow::Result<Vec<HashMap<u32, u32>>>>()
+        });
+
+        match results {
+            Ok(results) => Ok(results.into_iter().flatten().collect()),
+            Err(err)
+                if is_unknown_field(&err, "workItems")
+                    || is_unknown_type(&err, "WorkItemWidgetHierarchy") =>
+            {
+                println!("Work items are not supported by this GitLab instance, skipping issue hierarchy");
+                Ok(HashMap::new())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Fetches the links to other issues of each of `issues`.
+    ///
+    /// Up to `jobs` issues are handled concurrently.
+    fn fetch_issue_links(&self, issues: &mut [Issue]) -> anyhow::Result<()> {
+        self.pool.install(|| {
+            issues.par_iter_mut().try_for_each(|issue| {
+                let links: Vec<IssueLink> = self.fetch(&self.client, &*issue)?;
+                issue.links = Some(links);
+                Ok(())
+            })
+        })
+    }
+
+    /// Fetches the issues closed by and related to each of `merge_requests`.
+    ///
+    /// Up to `jobs` merge requests are handled concurrently.
+    fn fetch_merge_request_issues(
+        &self,
+        merge_requests: &mut [MergeRequest],
+    ) -> anyhow::Result<()> {
+        self.pool.install(|| {
+            merge_requests.par_iter_mut().try_for_each(|merge_request| {
+                let issue_ids: MergeRequestIssueIds = self.fetch(&self.client, &*merge_request)?;
+                merge_request.issue_ids = Some(issue_ids);
+                Ok(())
+            })
+        })
+    }
+}
+
+/// Whether `err` is GitLab rejecting a query for using `field`, which older versions do not know
+fn is_unknown_field(err: &anyhow::Error, field: &str) -> bool {
+    match err.downcast_ref::<FetchError>() {
+        Some(FetchError::GraphQLError { errors, .. }) => errors.iter().any(|error| {
+            error
+                .message
+                .contains(&format!("Field '{}' doesn't exist", field))
+        }),
+        _ => false,
+    }
+}
+
+/// Whether `err` is GitLab rejecting a query for using the type `name`, which older versions do not
+/// know
+fn is_unknown_type(err: &anyhow::Error, name: &str) -> bool {
+    match err.downcast_ref::<FetchError>() {
+        Some(FetchError::GraphQLError { errors, .. }) => errors
+            .iter()
+            .any(|error| error.message.contains(&format!("No such type {},", name))),
+        _ => false,
+    }
 }
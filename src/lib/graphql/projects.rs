@@ -1,5 +1,5 @@
 /// Currently unused, because fetching single projects is not supported yet
-use graphql_client::{GraphQLQuery, Response};
+use graphql_client::GraphQLQuery;
 
 use super::{Fetch, Fetcher};
 use crate::{model::Project, graphql::{Id, IdError}};
@@ -30,9 +30,7 @@ impl Fetch<Vec<Project>, &[u32]> for Fetcher {
             .collect();
 
         let query = ProjectsQuery::build_query(q::Variables { project_ids });
-        let resp = client.post(&self.base_uri).json(&query).send()?;
-        let response_body: Response<q::ResponseData> = resp.json()?;
-        let data: q::ResponseData = response_body.data.unwrap();
+        let data: q::ResponseData = self.post(client, &query, "projects", None)?;
         let projects: q::ProjectsQueryProjects = data.projects.unwrap();
         let nodes = projects.nodes.unwrap();
 
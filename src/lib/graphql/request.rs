@@ -0,0 +1,278 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use graphql_client::{QueryBody, Response};
+use reqwest::{
+    blocking::{self, Client, RequestBuilder},
+    header::HeaderMap,
+    StatusCode,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::Fetcher;
+use crate::{FetchError, GraphQLError, RetryOptions};
+
+impl Fetcher {
+    /// Posts `query` about `subject` (a group or project path) to the GraphQL API and returns the
+    /// response's data.
+    ///
+    /// If GitLab reports any errors, they are returned as [`FetchError::GraphQLError`], naming
+    /// `subject` and the `cursor` of the requested page. Partial data sent along with errors is
+    /// discarded, as fields failing to resolve are just `null` in it.
+    pub(super) fn post<V: Serialize, R: DeserializeOwned>(
+        &self,
+        client: &Client,
+        query: &QueryBody<V>,
+        subject: &str,
+        cursor: Option<&str>,
+    ) -> anyhow::Result<R> {
+        let response: Response<R> = self
+            .send(|| client.post(&self.base_uri).json(query))
+            .and_then(|resp| resp.json().context("API response is no valid JSON"))
+            .with_context(|| format!("Cannot fetch {} of '{}'", query.operation_name, subject))?;
+
+        let errors = response.errors.unwrap_or_default();
+        if errors.is_empty() {
+            return response.data.ok_or_else(|| {
+                FetchError::FetchError(format!(
+                    "No data on {} response for '{}'",
+                    query.operation_name, subject
+                ))
+                .into()
+            });
+        }
+
+        let error = FetchError::GraphQLError {
+            operation: query.operation_name.to_string(),
+            subject: subject.to_string(),
+            cursor: cursor.map(str::to_string),
+            errors: errors
+                .into_iter()
+                .map(|error| GraphQLError {
+                    path: error.path.map(|path| {
+                        path.iter()
+                            .map(|fragment| fragment.to_string())
+                            .collect::<Vec<_>>()
+                            .join("/")
+                    }),
+                    message: error.message,
+                })
+                .collect(),
+        };
+        Err(error.into())
+    }
+
+    /// Fetches all pages of the list at `path` (relative to `/api/v4/`) from the REST API.
+    ///
+    /// Used for data the GraphQL API does not expose.
+    pub(super) fn get_all<R: DeserializeOwned>(
+        &self,
+        client: &Client,
+        path: &str,
+    ) -> anyhow::Result<Vec<R>> {
+        let url = self.rest_url(path)?;
+        let mut items = vec![];
+
+        let mut page = Some("1".to_string());
+        while let Some(current) = page {
+            let resp = self
+                .send(|| {
+                    client
+                        .get(url.clone())
+                        .query(&[("per_page", "100"), ("page", current.as_str())])
+                })
+                .and_then(|resp| resp.error_for_status().map_err(anyhow::Error::from))
+                .with_context(|| format!("Cannot fetch '{}' (page {})", path, current))?;
+            // GitLab leaves `X-Next-Page` empty on the last page
+            page = resp
+                .headers()
+                .get("X-Next-Page")
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string);
+            let new_items: Vec<R> = resp
+                .json()
+                .with_context(|| format!("Response for '{}' is no valid JSON list", path))?;
+            items.extend(new_items);
+        }
+
+        Ok(items)
+    }
+
+    /// Sends the request built by `request` and returns the response.
+    ///
+    /// Timeouts, connection errors, rate limited requests (`429`) and server errors (`5xx`) are
+    /// retried with exponential backoff. If GitLab announces when to retry via `Retry-After` or
+    /// `RateLimit-Reset`, that time is waited for instead. A response exhausting the rate limit
+    /// (`RateLimit-Remaining: 0`) delays the caller until the limit is reset.
+    fn send(&self, request: impl Fn() -> RequestBuilder) -> anyhow::Result<blocking::Response> {
+        let retry = &self.options.retry;
+        let mut retries = 0;
+        loop {
+            let (reason, delay) = match request().send() {
+                Ok(resp) if is_transient(resp.status()) => {
+                    let reason = format!("API responded with status {}", resp.status());
+                    if retries >= retry.max_retries {
+                        return Err(FetchError::FetchError(reason).into());
+                    }
+                    let delay =
+                        announced_delay(resp.headers()).unwrap_or_else(|| backoff(retry, retries));
+                    (reason, delay)
+                }
+                Ok(resp) => {
+                    if let Some(delay) = rate_limit_delay(resp.headers()) {
+                        println!(
+                            "Rate limit exhausted, waiting {}s for it to reset",
+                            delay.as_secs()
+                        );
+                        std::thread::sleep(delay);
+                    }
+                    return Ok(resp);
+                }
+                Err(err)
+                    if (err.is_timeout() || err.is_connect() || err.is_request())
+                        && retries < retry.max_retries =>
+                {
+                    (err.to_string(), backoff(retry, retries))
+                }
+                Err(err) => return Err(err).context("Cannot make API request"),
+            };
+
+            retries += 1;
+            println!(
+                "{}, retry {}/{} in {:.1}s",
+                reason,
+                retries,
+                retry.max_retries,
+                delay.as_secs_f64()
+            );
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Whether a request failing with `status` may succeed if it is sent again
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Exponential backoff before retry number `retries + 1`
+fn backoff(retry: &RetryOptions, retries: u32) -> Duration {
+    retry
+        .initial_delay
+        .saturating_mul(2u32.saturating_pow(retries))
+        .min(retry.max_delay)
+}
+
+/// Time GitLab asks to wait before the next request, taken from `Retry-After` (in seconds) or
+/// `RateLimit-Reset` (as Unix timestamp)
+fn announced_delay(headers: &HeaderMap) -> Option<Duration> {
+    header_value(headers, "Retry-After")
+        .map(Duration::from_secs)
+        .or_else(|| until_reset(headers))
+}
+
+/// Time until the rate limit is reset, if the last request used up the remaining quota
+fn rate_limit_delay(headers: &HeaderMap) -> Option<Duration> {
+    match header_value(headers, "RateLimit-Remaining") {
+        Some(0) => until_reset(headers),
+        _ => None,
+    }
+}
+
+fn until_reset(headers: &HeaderMap) -> Option<Duration> {
+    let reset = Duration::from_secs(header_value(headers, "RateLimit-Reset")?);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    Some(reset.saturating_sub(now))
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(values: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max_delay() {
+        let retry = RetryOptions {
+            max_retries: 10,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        };
+
+        let delays: Vec<u64> = (0..8)
+            .map(|retries| backoff(&retry, retries).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff(&retry, u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn retry_after_takes_precedence_over_rate_limit_reset() {
+        let reset = (now() + 120).to_string();
+        let headers = headers(&[("Retry-After", "7"), ("RateLimit-Reset", &reset)]);
+
+        assert_eq!(announced_delay(&headers), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn rate_limit_reset_is_used_without_retry_after() {
+        let reset = (now() + 120).to_string();
+        let delay = announced_delay(&headers(&[("RateLimit-Reset", &reset)])).unwrap();
+
+        assert!(delay > Duration::from_secs(110) && delay <= Duration::from_secs(120));
+    }
+
+    #[test]
+    fn non_numeric_retry_after_is_ignored() {
+        let headers = headers(&[("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT")]);
+
+        assert_eq!(header_value(&headers, "Retry-After"), None);
+        assert_eq!(announced_delay(&headers), None);
+    }
+
+    #[test]
+    fn exhausted_rate_limit_waits_for_future_reset() {
+        let reset = (now() + 30).to_string();
+        let headers = headers(&[("RateLimit-Remaining", "0"), ("RateLimit-Reset", &reset)]);
+        let delay = rate_limit_delay(&headers).unwrap();
+
+        assert!(delay > Duration::from_secs(20) && delay <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn exhausted_rate_limit_with_past_reset_does_not_wait() {
+        let reset = (now() - 30).to_string();
+        let headers = headers(&[("RateLimit-Remaining", "0"), ("RateLimit-Reset", &reset)]);
+
+        assert_eq!(rate_limit_delay(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn remaining_rate_limit_does_not_wait() {
+        let reset = (now() + 30).to_string();
+        let headers = headers(&[("RateLimit-Remaining", "5"), ("RateLimit-Reset", &reset)]);
+
+        assert_eq!(rate_limit_delay(&headers), None);
+        assert_eq!(rate_limit_delay(&HeaderMap::new()), None);
+    }
+}
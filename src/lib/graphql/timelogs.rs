@@ -0,0 +1,102 @@
+use graphql_client::GraphQLQuery;
+
+use super::{Fetch, Fetcher};
+use crate::{
+    model::{Issue, MergeRequest, TimeLog},
+    DateRange, FetchError,
+};
+
+type Time = String;
+type TimelogID = String;
+type NoteID = String;
+
+// The paths are relative to the directory where your `Cargo.toml` is located.
+// Both json and the GraphQL schema language are supported as sources for the schema
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./group_timelogs_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    "Default",
+    skip_serializing_none
+)]
+pub(crate) struct GroupTimelogsQuery;
+
+/// Fetches all time logs of a group (including its subgroups) within `range`.
+///
+/// Issues and merge requests are derived from the time logs, so only those with time logs are
+/// returned.
+impl Fetch<(Vec<Issue>, Vec<MergeRequest>, Vec<TimeLog>), (String, DateRange)> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        (full_path, range): (String, DateRange),
+    ) -> anyhow::Result<(Vec<Issue>, Vec<MergeRequest>, Vec<TimeLog>)> {
+        use group_timelogs_query as q;
+
+        let mut issues = vec![];
+        let mut merge_requests = vec![];
+        let mut time_logs = vec![];
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = GroupTimelogsQuery::build_query(q::Variables {
+                full_path: full_path.clone(),
+                start_date: range.since.clone(),
+                end_date: range.until.clone(),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData = self.post(client, &query, &full_path, cursor.as_deref())?;
+            let group = data.group.ok_or_else(|| {
+                FetchError::from("No group data on group timelogs query response")
+            })?;
+            let timelogs = group.timelogs;
+
+            let has_next_page = timelogs.page_info.has_next_page;
+            let end_cursor = timelogs.page_info.end_cursor.clone();
+
+            for time_log in timelogs
+                .nodes
+                .as_ref()
+                .ok_or_else(|| FetchError::from("No 'nodes' in timeLog found"))?
+                .iter()
+                .flatten()
+            {
+                let issue_id = match time_log.issue.as_ref() {
+                    Some(issue) => {
+                        let issue = issue!(issue, issue.project_id as u32, group_timelogs_query);
+                        let id = issue.id;
+                        issues.push(issue);
+                        Some(id)
+                    }
+                    None => None,
+                };
+
+                let merge_request_id = match time_log.merge_request.as_ref() {
+                    Some(merge_request) => {
+                        let merge_request = merge_request!(
+                            merge_request,
+                            merge_request.project_id as u32,
+                            group_timelogs_query
+                        );
+                        let id = merge_request.id;
+                        merge_requests.push(merge_request);
+                        Some(id)
+                    }
+                    None => None,
+                };
+
+                time_logs.push(time_log!(time_log, issue_id, merge_request_id));
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok((issues, merge_requests, time_logs))
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use graphql_client::{GraphQLQuery, Response};
+use graphql_client::GraphQLQuery;
 
 use super::{Fetch, Fetcher};
 use crate::{
@@ -21,6 +21,13 @@ use crate::{
 )]
 pub(crate) struct UsersQuery;
 
+/// GitLab returns at most this many users per request
+const USERS_PER_REQUEST: usize = 100;
+
+/// Fetches the users with the given ids.
+///
+/// Users GitLab cannot resolve (e.g. deleted users) are returned as placeholders, so everything
+/// referencing them can still be stored.
 impl Fetch<Vec<User>, HashSet<u32>> for Fetcher {
     fn fetch(
         &self,
@@ -29,37 +36,64 @@ impl Fetch<Vec<User>, HashSet<u32>> for Fetcher {
     ) -> anyhow::Result<Vec<User>> {
         use users_query as q;
 
-        let user_ids = ids
-            .iter()
-            .map(|id| format!("gid://gitlab/User/{}", id))
-            .collect();
+        let mut ids: Vec<u32> = ids.into_iter().collect();
+        ids.sort();
 
-        let query = UsersQuery::build_query(q::Variables { user_ids });
-        let resp = client.post(&self.base_uri).json(&query).send()?;
-        let response_body: Response<q::ResponseData> = resp.json()?;
-        let data: q::ResponseData = response_body
-            .data
-            .ok_or_else(|| FetchError::from("No data on user query response"))?;
-        let users = data.users.ok_or_else(|| {
-            FetchError::from("No users data on user query response's data object")
-        })?;
-        let nodes = users.nodes.ok_or_else(|| {
-            FetchError::from("No nodes data on user query response's user object")
-        })?;
+        let mut result = vec![];
+        for chunk in ids.chunks(USERS_PER_REQUEST) {
+            let user_ids = chunk
+                .iter()
+                .map(|id| format!("gid://gitlab/User/{}", id))
+                .collect();
 
-        let result = nodes
-            .into_iter()
-            .flatten()
-            .try_fold(vec![], |mut vec, node| {
-                let Id(id) = Id::try_from(node.id.as_str())?;
-                let user = User {
-                    id,
-                    username: node.username,
-                };
-                vec.push(user);
-                Ok::<Vec<User>, IdError>(vec)
+            let query = UsersQuery::build_query(q::Variables { user_ids });
+            let data: q::ResponseData =
+                self.post(client, &query, &format!("{} users", chunk.len()), None)?;
+            let users = data.users.ok_or_else(|| {
+                FetchError::from("No users data on user query response's data object")
+            })?;
+            let nodes = users.nodes.ok_or_else(|| {
+                FetchError::from("No nodes data on user query response's user object")
             })?;
 
+            let users = nodes
+                .into_iter()
+                .flatten()
+                .try_fold(vec![], |mut vec, node| {
+                    let Id(id) = Id::try_from(node.id.as_str())?;
+                    let user = User {
+                        id,
+                        username: node.username,
+                        name: Some(node.name),
+                        public_email: node.public_email,
+                        state: Some(user_state(&node.state)),
+                        bot: node.bot,
+                        // Avatars uploaded to the instance have a relative URL
+                        avatar_url: node.avatar_url.and_then(|url| self.web_url(&url)),
+                        placeholder: false,
+                    };
+                    vec.push(user);
+                    Ok::<Vec<User>, IdError>(vec)
+                })?;
+            result.extend(users);
+        }
+
+        let resolved: HashSet<u32> = result.iter().map(|user| user.id).collect();
+        let unresolved = ids.into_iter().filter(|id| !resolved.contains(id));
+        result.extend(unresolved.map(User::placeholder));
+
         Ok(result)
     }
 }
+
+fn user_state(state: &users_query::UserState) -> String {
+    use users_query::UserState;
+
+    match state {
+        UserState::active => "active",
+        UserState::blocked => "blocked",
+        UserState::deactivated => "deactivated",
+        UserState::Other(state) => state,
+    }
+    .to_string()
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use graphql_client::GraphQLQuery;
+
+use super::{Fetch, Fetcher};
+use crate::{graphql::Id, model::Project, FetchError};
+
+type WorkItemID = String;
+
+// The paths are relative to the directory where your `Cargo.toml` is located.
+// Both json and the GraphQL schema language are supported as sources for the schema
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "./gitlab_schema.json",
+    query_path = "./project_work_items_query.graphql",
+    response_derives = "Debug",
+    "Clone",
+    skip_serializing_none
+)]
+pub(crate) struct ProjectWorkItemsQuery;
+
+/// Fetches the parent of every work item of a project that has one, keyed by the child's id.
+///
+/// Work items share their ids with issues, so tasks can be linked to their parent issue.
+impl Fetch<HashMap<u32, u32>, &Project> for Fetcher {
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        project: &Project,
+    ) -> anyhow::Result<HashMap<u32, u32>> {
+        use project_work_items_query as q;
+        use q::ProjectWorkItemsQueryProjectWorkItemsNodesWidgets as Widget;
+
+        let mut parents = HashMap::new();
+
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = ProjectWorkItemsQuery::build_query(q::Variables {
+                full_path: project.full_path.clone(),
+                cursor: cursor.clone(),
+            });
+            let data: q::ResponseData =
+                self.post(client, &query, &project.full_path, cursor.as_deref())?;
+            let project = data.project.ok_or_else(|| {
+                FetchError::from("No project data on project work items query response")
+            })?;
+            let Some(work_items) = project.work_items else {
+                break;
+            };
+
+            let has_next_page = work_items.page_info.has_next_page;
+            let end_cursor = work_items.page_info.end_cursor.clone();
+
+            for work_item in work_items.nodes.iter().flatten().flatten() {
+                for widget in work_item.widgets.iter().flatten() {
+                    let Widget::WorkItemWidgetHierarchy(hierarchy) = widget else {
+                        continue;
+                    };
+                    if let Some(parent) = &hierarchy.parent {
+                        let Id(id) = Id::try_from(work_item.id.as_str())?;
+                        let Id(parent_id) = Id::try_from(parent.id.as_str())?;
+                        parents.insert(id, parent_id);
+                    }
+                }
+            }
+
+            if has_next_page {
+                cursor = end_cursor.clone();
+            } else {
+                break;
+            };
+        }
+
+        Ok(parents)
+    }
+}
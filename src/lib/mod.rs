@@ -2,23 +2,178 @@ mod db;
 mod graphql;
 mod model;
 
+use std::time::Duration;
+
 use model::*;
 use thiserror::Error;
 
 #[derive(Debug, Default)]
 pub(crate) struct FetchResult {
+    pub(crate) groups: Vec<Group>,
     pub(crate) projects: Vec<Project>,
     pub(crate) milestones: Vec<Milestone>,
+    pub(crate) epics: Vec<Epic>,
+    pub(crate) iteration_cadences: Vec<IterationCadence>,
+    pub(crate) iterations: Vec<Iteration>,
     pub(crate) issues: Vec<Issue>,
     pub(crate) merge_requests: Vec<MergeRequest>,
+    pub(crate) labels: Vec<Label>,
     pub(crate) time_logs: Vec<TimeLog>,
     pub(crate) users: Vec<User>,
+    pub(crate) memberships: Vec<Membership>,
+}
+
+/// Range of days (`YYYY-MM-DD`, both inclusive) to fetch time logs for
+#[derive(Debug, Default, Clone)]
+pub struct DateRange {
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+impl DateRange {
+    /// Whether the day of the (UTC) ISO 8601 timestamp `date` lies within the range
+    pub(crate) fn contains(&self, date: &str) -> bool {
+        let day = date.get(..10).unwrap_or(date);
+        self.since.as_deref().is_none_or(|since| day >= since)
+            && self.until.as_deref().is_none_or(|until| day <= until)
+    }
+
+    /// Whether every day of `other` lies within the range
+    pub(crate) fn covers(&self, other: &DateRange) -> bool {
+        self.since.as_deref().is_none_or(|since| {
+            other
+                .since
+                .as_deref()
+                .is_some_and(|other_since| other_since >= since)
+        }) && self.until.as_deref().is_none_or(|until| {
+            other
+                .until
+                .as_deref()
+                .is_some_and(|other_until| other_until <= until)
+        })
+    }
+}
+
+/// How issues, merge requests and time logs are crawled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FetchStrategy {
+    /// Fetch all issues and merge requests of every project, including their time logs
+    #[default]
+    Projects,
+    /// Fetch the time logs of the whole group and derive issues and merge requests from them.
+    /// Needs far fewer requests, but only yields issues and merge requests with time logs.
+    /// `FetchOptions::issue_links` and `FetchOptions::merge_request_issues` still add requests per
+    /// issue and merge request.
+    GroupTimelogs,
+}
+
+/// Type of an issue (work item)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueType {
+    Issue,
+    Incident,
+    TestCase,
+    Requirement,
+    Task,
+}
+
+impl IssueType {
+    /// Name of the type as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueType::Issue => "issue",
+            IssueType::Incident => "incident",
+            IssueType::TestCase => "test_case",
+            IssueType::Requirement => "requirement",
+            IssueType::Task => "task",
+        }
+    }
+}
+
+/// How failed API requests are retried
+#[derive(Debug, Clone)]
+pub struct RetryOptions {
+    /// Maximum number of retries of a single request
+    pub max_retries: u32,
+    /// Delay before the first retry. It is doubled for every further retry.
+    pub initial_delay: Duration,
+    /// Upper bound of the delay between two retries, unless GitLab asks to wait longer
+    pub max_delay: Duration,
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FetchOptions {
+    /// Also fetch projects and milestones of all subgroups
+    pub include_subgroups: bool,
+    /// Only fetch time logs spent within this range
+    pub range: DateRange,
+    pub strategy: FetchStrategy,
+    /// Only fetch issues of these types, along with their time logs. Empty to fetch all issues.
+    pub issue_types: Vec<IssueType>,
+    /// Fetch the issues closed by and related to every merge request, with two REST requests per
+    /// merge request
+    pub merge_request_issues: bool,
+    /// Fetch the links of every issue to other issues, with one REST request per issue
+    pub issue_links: bool,
+    /// Maximum number of projects fetched concurrently (`0` lets rayon decide)
+    pub jobs: usize,
+    pub retry: RetryOptions,
 }
 
 #[derive(Debug, Error)]
 pub enum FetchError {
     #[error("Error while fetching API: {0}")]
     FetchError(String),
+    /// GitLab answered a query with an `errors` list instead of (complete) data
+    #[error(
+        "GitLab reported errors for {operation} of '{subject}'{}:{}",
+        describe_cursor(.cursor),
+        describe_errors(.errors)
+    )]
+    GraphQLError {
+        /// Name of the query
+        operation: String,
+        /// Group, project, issue or merge request the query was made for
+        subject: String,
+        /// Cursor of the requested page, if not the first one
+        cursor: Option<String>,
+        errors: Vec<GraphQLError>,
+    },
+}
+
+/// Entry of the `errors` list of a GraphQL response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphQLError {
+    pub message: String,
+    /// Response field the error belongs to, like `project/issues/nodes/0/timelogs`
+    pub path: Option<String>,
+}
+
+fn describe_cursor(cursor: &Option<String>) -> String {
+    match cursor {
+        Some(cursor) => format!(" (page after cursor '{}')", cursor),
+        None => String::new(),
+    }
+}
+
+fn describe_errors(errors: &[GraphQLError]) -> String {
+    errors
+        .iter()
+        .map(|error| match &error.path {
+            Some(path) => format!("\n  - {}: {}", path, error.message),
+            None => format!("\n  - {}", error.message),
+        })
+        .collect()
 }
 
 impl From<&str> for FetchError {
@@ -33,34 +188,108 @@ pub enum DbError {
     DbError(String),
 }
 
+/// Crawls the group `group_name` and writes the result to the database at `db_file_path`.
+///
+/// With `sync` an existing database is updated, otherwise it is replaced and kept as
+/// `<db_file_path>~`. The database is only written if the crawl succeeds.
 pub fn go(
     base_uri: String,
     personal_access_token: String,
     group_name: String,
+    options: FetchOptions,
+    sync: bool,
     db_file_path: String,
 ) -> anyhow::Result<()> {
     println!("URI: {}", base_uri);
     println!("Token: {}", personal_access_token);
+    if let Some(since) = &options.range.since {
+        println!("Since: {}", since);
+    }
+    if let Some(until) = &options.range.until {
+        println!("Until: {}", until);
+    }
+
+    let started_at = db::now()?;
+    let db_exists = std::path::Path::new(&db_file_path).exists();
+    let last_run = if sync && db_exists {
+        let conn = db::open(&db_file_path)?;
+        db::last_successful_run(&conn, &group_name)?
+    } else {
+        None
+    };
+    // Unchanged issues and merge requests only have the time logs within the last run's range
+    let updated_after = match last_run {
+        Some((started_at, last_range)) if last_range.covers(&options.range) => Some(started_at),
+        Some(_) => {
+            println!("The last run fetched fewer time logs than requested, fetching everything");
+            None
+        }
+        None => None,
+    };
+    if let Some(updated_after) = &updated_after {
+        println!("Syncing changes since {}", updated_after);
+    }
+
+    let range = options.range.clone();
+    let fetcher = graphql::Fetcher::new(base_uri, personal_access_token, options)?;
+    let result = fetcher.go(group_name.clone(), updated_after.clone())?;
 
-    let fetcher = graphql::Fetcher::new(base_uri, personal_access_token)?;
-    let FetchResult {
-        projects,
-        milestones,
-        issues,
-        merge_requests,
-        time_logs,
-        users,
-    } = fetcher.go(group_name)?;
-
-    db::save_to_db(
-        db_file_path,
-        projects,
-        milestones,
-        issues,
-        merge_requests,
-        time_logs,
-        users,
+    // The database is only touched once the crawl succeeded, so a failed run leaves no file behind
+    // and keeps the previous one in place
+    if db_exists && !sync {
+        std::fs::rename(&db_file_path, format!("{}~", db_file_path))?;
+    }
+    let mut conn = db::open(&db_file_path)?;
+    let run_id = db::start_run(
+        &conn,
+        &group_name,
+        &started_at,
+        updated_after.as_deref(),
+        &range,
     )?;
+    db::save_to_db(&mut conn, run_id, &range, result)?;
 
     Ok(())
 }
+
+/// Upgrades the database at `db_file_path` to the current schema version.
+///
+/// Returns the schema versions before and after the migration.
+pub fn migrate(db_file_path: String) -> anyhow::Result<(usize, usize)> {
+    if !std::path::Path::new(&db_file_path).exists() {
+        return Err(
+            DbError::DbError(format!("Database file '{}' does not exist", db_file_path)).into(),
+        );
+    }
+    let mut conn = rusqlite::Connection::open(db_file_path)?;
+    db::migrate(&mut conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(since: Option<&str>, until: Option<&str>) -> DateRange {
+        DateRange {
+            since: since.map(str::to_string),
+            until: until.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn unbounded_range_covers_every_range() {
+        let all = range(None, None);
+        assert!(all.covers(&range(None, None)));
+        assert!(all.covers(&range(Some("2024-06-01"), Some("2024-06-30"))));
+    }
+
+    #[test]
+    fn bounded_range_only_covers_ranges_within_it() {
+        let june = range(Some("2024-06-01"), Some("2024-06-30"));
+        assert!(june.covers(&june));
+        assert!(june.covers(&range(Some("2024-06-10"), Some("2024-06-20"))));
+        assert!(!june.covers(&range(None, None)));
+        assert!(!june.covers(&range(Some("2024-05-31"), Some("2024-06-30"))));
+        assert!(!june.covers(&range(Some("2024-06-01"), None)));
+    }
+}
@@ -6,10 +6,18 @@ pub(crate) trait Unique {
     fn get_key(&self) -> Self::Key;
 }
 
+/// Sorts `items` by their key and removes all but the first item of each key
+pub(crate) fn dedup<T: Unique>(items: &mut Vec<T>) {
+    items.sort_by_key(|item| item.get_key());
+    items.dedup_by_key(|item| item.get_key());
+}
+
 #[derive(Deserialize, Debug, PartialEq)]
 pub(crate) struct Group {
     pub(crate) id: u32,
-    pub(crate) projects: Vec<Project>,
+    pub(crate) name: String,
+    pub(crate) full_path: String,
+    pub(crate) parent_id: Option<u32>,
 }
 
 impl Unique for Group {
@@ -24,6 +32,9 @@ impl Unique for Group {
 pub(crate) struct Project {
     pub(crate) id: u32,
     pub(crate) name: String,
+    pub(crate) full_path: String,
+    pub(crate) web_url: Option<String>,
+    pub(crate) group_id: Option<u32>,
 }
 
 impl Unique for Project {
@@ -38,6 +49,32 @@ impl Unique for Project {
 pub(crate) struct Milestone {
     pub(crate) id: u32,
     pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) state: MilestoneState,
+    pub(crate) start_date: Option<String>,
+    pub(crate) due_date: Option<String>,
+    pub(crate) web_url: Option<String>,
+    /// Owning group of a group milestone
+    pub(crate) group_id: Option<u32>,
+    /// Owning project of a project milestone
+    pub(crate) project_id: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MilestoneState {
+    #[default]
+    Active,
+    Closed,
+}
+
+impl MilestoneState {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            MilestoneState::Active => "active",
+            MilestoneState::Closed => "closed",
+        }
+    }
 }
 
 impl Unique for Milestone {
@@ -48,6 +85,72 @@ impl Unique for Milestone {
     }
 }
 
+#[derive(Deserialize, Debug, PartialEq)]
+pub(crate) struct Epic {
+    pub(crate) id: u32,
+    pub(crate) iid: u32,
+    pub(crate) name: String,
+    pub(crate) group_id: u32,
+    pub(crate) parent_id: Option<u32>,
+    /// `opened` or `closed`
+    pub(crate) state: String,
+    pub(crate) start_date: Option<String>,
+    pub(crate) due_date: Option<String>,
+    pub(crate) created_at: Option<String>,
+    pub(crate) closed_at: Option<String>,
+    pub(crate) web_url: String,
+}
+
+impl Unique for Epic {
+    type Key = u32;
+
+    fn get_key(&self) -> Self::Key {
+        self.id
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub(crate) struct IterationCadence {
+    pub(crate) id: u32,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) duration_in_weeks: Option<i64>,
+    pub(crate) automatic: Option<bool>,
+    pub(crate) active: Option<bool>,
+    pub(crate) start_date: Option<String>,
+}
+
+impl Unique for IterationCadence {
+    type Key = u32;
+
+    fn get_key(&self) -> Self::Key {
+        self.id
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub(crate) struct Iteration {
+    pub(crate) id: u32,
+    pub(crate) iid: u32,
+    /// Iterations of automatic cadences have no title, only their dates
+    pub(crate) name: Option<String>,
+    pub(crate) description: Option<String>,
+    /// `upcoming`, `current` or `closed`
+    pub(crate) state: String,
+    pub(crate) start_date: Option<String>,
+    pub(crate) due_date: Option<String>,
+    pub(crate) web_url: String,
+    pub(crate) cadence_id: u32,
+}
+
+impl Unique for Iteration {
+    type Key = u32;
+
+    fn get_key(&self) -> Self::Key {
+        self.id
+    }
+}
+
 #[derive(Deserialize, Debug, PartialEq)]
 pub(crate) struct Issue {
     pub(crate) id: u32,
@@ -55,6 +158,28 @@ pub(crate) struct Issue {
     pub(crate) name: String,
     pub(crate) project_id: u32,
     pub(crate) milestone_id: Option<u32>,
+    pub(crate) epic_id: Option<u32>,
+    pub(crate) iteration_id: Option<u32>,
+    /// `issue`, `incident`, `test_case`, `requirement` or `task`
+    pub(crate) issue_type: String,
+    /// `opened`, `closed` or `locked`
+    pub(crate) state: String,
+    pub(crate) created_at: String,
+    pub(crate) closed_at: Option<String>,
+    /// Estimated time in seconds
+    pub(crate) time_estimate: i64,
+    /// Total time spent in seconds, including time logs outside of the fetched range
+    pub(crate) total_time_spent: i64,
+    pub(crate) weight: Option<i64>,
+    pub(crate) due_date: Option<String>,
+    pub(crate) web_url: String,
+    pub(crate) author_id: u32,
+    pub(crate) assignee_ids: Vec<u32>,
+    pub(crate) labels: Vec<Label>,
+    /// Parent in the work item hierarchy, like the issue a task belongs to
+    pub(crate) parent_id: Option<u32>,
+    /// Links to other issues. `None` if they were not fetched.
+    pub(crate) links: Option<Vec<IssueLink>>,
 }
 
 impl Unique for Issue {
@@ -65,6 +190,15 @@ impl Unique for Issue {
     }
 }
 
+/// A link from an issue to another issue
+#[derive(Deserialize, Debug, PartialEq)]
+pub(crate) struct IssueLink {
+    #[serde(rename = "id")]
+    pub(crate) issue_id: u32,
+    /// `relates_to`, `blocks` or `is_blocked_by`, as seen from the linking issue
+    pub(crate) link_type: String,
+}
+
 #[derive(Deserialize, Debug, PartialEq)]
 pub(crate) struct MergeRequest {
     pub(crate) id: u32,
@@ -72,6 +206,25 @@ pub(crate) struct MergeRequest {
     pub(crate) name: String,
     pub(crate) project_id: u32,
     pub(crate) milestone_id: Option<u32>,
+    /// `opened`, `closed`, `locked` or `merged`
+    pub(crate) state: String,
+    pub(crate) merged_at: Option<String>,
+    pub(crate) source_branch: String,
+    pub(crate) target_branch: String,
+    pub(crate) web_url: Option<String>,
+    pub(crate) author_id: Option<u32>,
+    pub(crate) assignee_ids: Vec<u32>,
+    pub(crate) reviewer_ids: Vec<u32>,
+    pub(crate) labels: Vec<Label>,
+    /// Issues closed by the merge request once it is merged and issues it only mentions. `None`
+    /// if they were not fetched.
+    pub(crate) issue_ids: Option<MergeRequestIssueIds>,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct MergeRequestIssueIds {
+    pub(crate) closes: Vec<u32>,
+    pub(crate) related: Vec<u32>,
 }
 
 impl Unique for MergeRequest {
@@ -84,18 +237,68 @@ impl Unique for MergeRequest {
 
 #[derive(Deserialize, Debug, PartialEq)]
 pub(crate) struct TimeLog {
-    pub(crate) time: i32,
+    pub(crate) id: u32,
+    pub(crate) seconds: i64,
+    /// Description of the spent time, as entered by the user
+    pub(crate) summary: Option<String>,
+    /// Id of the note (comment) the time was logged with
+    pub(crate) note_id: Option<u32>,
+    /// When the note the time was logged with was created, which may differ from the day the time
+    /// was spent. `None` for time logs without a note.
+    pub(crate) note_created_at: Option<String>,
     pub(crate) date: String,
     pub(crate) user_id: u32,
     pub(crate) issue_id: Option<u32>,
     pub(crate) merge_request_id: Option<u32>,
 }
 
+impl TimeLog {
+    pub(crate) fn minutes(&self) -> f64 {
+        self.seconds as f64 / 60.0
+    }
+
+    pub(crate) fn hours(&self) -> f64 {
+        self.seconds as f64 / 3600.0
+    }
+
+    /// Negative time logs are corrections of previously logged time (e.g. `/spend -30m`)
+    pub(crate) fn is_negative(&self) -> bool {
+        self.seconds < 0
+    }
+}
+
 impl Unique for TimeLog {
-    type Key = (u32, String);
+    type Key = u32;
 
     fn get_key(&self) -> Self::Key {
-        (self.user_id, self.date.clone())
+        self.id
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct Label {
+    pub(crate) id: u32,
+    pub(crate) title: String,
+    /// Background color, like `#dc143c`
+    pub(crate) color: String,
+    pub(crate) description: Option<String>,
+}
+
+impl Label {
+    /// Splits a scoped label like `type::bug` into its scope and value.
+    ///
+    /// As in GitLab, the scope ends at the last `::`, so `priority::high::p1` has scope
+    /// `priority::high`.
+    pub(crate) fn scope_and_value(&self) -> Option<(&str, &str)> {
+        self.title.rsplit_once("::")
+    }
+}
+
+impl Unique for Label {
+    type Key = u32;
+
+    fn get_key(&self) -> Self::Key {
+        self.id
     }
 }
 
@@ -103,6 +306,30 @@ impl Unique for TimeLog {
 pub(crate) struct User {
     pub(crate) id: u32,
     pub(crate) username: String,
+    pub(crate) name: Option<String>,
+    pub(crate) public_email: Option<String>,
+    /// `active`, `blocked` or `deactivated`
+    pub(crate) state: Option<String>,
+    pub(crate) bot: bool,
+    pub(crate) avatar_url: Option<String>,
+    /// Stands in for a user GitLab could not resolve (e.g. a deleted user), so no time log is
+    /// orphaned
+    pub(crate) placeholder: bool,
+}
+
+impl User {
+    pub(crate) fn placeholder(id: u32) -> Self {
+        User {
+            id,
+            username: format!("unknown-user-{}", id),
+            name: None,
+            public_email: None,
+            state: None,
+            bot: false,
+            avatar_url: None,
+            placeholder: true,
+        }
+    }
 }
 
 impl Unique for User {
@@ -112,3 +339,86 @@ impl Unique for User {
         self.id
     }
 }
+
+/// Membership of a user in a group or project
+#[derive(Deserialize, Debug, PartialEq)]
+pub(crate) struct Membership {
+    pub(crate) user_id: u32,
+    pub(crate) group_id: Option<u32>,
+    pub(crate) project_id: Option<u32>,
+    /// GitLab's numeric access level, like `30` for developers
+    pub(crate) access_level: Option<i64>,
+    pub(crate) access_level_name: Option<AccessLevel>,
+    /// Whether the membership is inherited from an ancestor group
+    pub(crate) inherited: bool,
+    pub(crate) created_at: Option<String>,
+    pub(crate) expires_at: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum AccessLevel {
+    NoAccess,
+    MinimalAccess,
+    Guest,
+    Planner,
+    Reporter,
+    Developer,
+    Maintainer,
+    Owner,
+    /// Access levels introduced by later GitLab versions
+    #[serde(other)]
+    Other,
+}
+
+impl AccessLevel {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            AccessLevel::NoAccess => "no_access",
+            AccessLevel::MinimalAccess => "minimal_access",
+            AccessLevel::Guest => "guest",
+            AccessLevel::Planner => "planner",
+            AccessLevel::Reporter => "reporter",
+            AccessLevel::Developer => "developer",
+            AccessLevel::Maintainer => "maintainer",
+            AccessLevel::Owner => "owner",
+            AccessLevel::Other => "other",
+        }
+    }
+}
+
+impl Unique for Membership {
+    type Key = (u32, Option<u32>, Option<u32>);
+
+    fn get_key(&self) -> Self::Key {
+        (self.user_id, self.group_id, self.project_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(title: &str) -> Label {
+        Label {
+            id: 1,
+            title: title.to_string(),
+            color: "#dc143c".to_string(),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn scope_ends_at_last_separator() {
+        assert_eq!(
+            label("priority::high::p1").scope_and_value(),
+            Some(("priority::high", "p1"))
+        );
+        assert_eq!(label("type::bug").scope_and_value(), Some(("type", "bug")));
+    }
+
+    #[test]
+    fn unscoped_labels_have_no_scope() {
+        assert_eq!(label("bug").scope_and_value(), None);
+    }
+}
@@ -1,16 +1,27 @@
-use clap::Parser;
-use cli_args::CliArgs;
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use cli_args::{CliArgs, Command, DbCommand, IssueType, Strategy};
+use gitlab_timereport::{DateRange, FetchOptions, FetchStrategy, RetryOptions};
+use std::time::Duration;
 
 mod cli_args;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = CliArgs::parse();
 
+    if let Some(command) = args.command {
+        return match command {
+            Command::Db(DbCommand::Migrate { file }) => migrate(file),
+        };
+    }
+
+    // clap only lets these be missing if a subcommand is given
+    let (Some(uri), Some(token), Some(group)) = (args.uri, args.token, args.group) else {
+        unreachable!("--uri, --token and --group are required");
+    };
+
     let out_file = args.out_file.unwrap_or_else(|| {
-        let file_name: String = args
-            .group
+        let file_name: String = group
             .chars()
-            .into_iter()
             .map(|c| {
                 if c.is_ascii() {
                     c.to_ascii_lowercase()
@@ -22,22 +33,61 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         format!("{}.sqlite", file_name)
     });
 
-    if std::path::Path::new(&out_file).exists() {
-        if !args.force {
-            println!(
-                "Out file '{}' already exists. Use --force to overwrite.",
-                out_file
-            );
-            return Ok(());
-        } else {
-            std::fs::rename(&out_file, format!("{}~", out_file))?;
+    if let (Some(since), Some(until)) = (&args.since, &args.until) {
+        if since > until {
+            CliArgs::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("--since {} is after --until {}", since, until),
+                )
+                .exit();
         }
     }
 
+    // With --force, the existing file is moved aside once the crawl succeeded
+    if std::path::Path::new(&out_file).exists() && !args.sync && !args.force {
+        println!(
+            "Out file '{}' already exists. Use --force to overwrite or --sync to update it.",
+            out_file
+        );
+        return Ok(());
+    }
+
     gitlab_timereport::go(
-        args.uri.strip_suffix('/').unwrap_or(&args.uri).to_string(),
-        args.token,
-        args.group,
+        uri.strip_suffix('/').unwrap_or(&uri).to_string(),
+        token,
+        group,
+        FetchOptions {
+            include_subgroups: args.include_subgroups,
+            range: DateRange {
+                since: args.since,
+                until: args.until,
+            },
+            strategy: match args.strategy {
+                Strategy::Projects => FetchStrategy::Projects,
+                Strategy::GroupTimelogs => FetchStrategy::GroupTimelogs,
+            },
+            issue_types: args
+                .issue_types
+                .iter()
+                .map(|issue_type| match issue_type {
+                    IssueType::Issue => gitlab_timereport::IssueType::Issue,
+                    IssueType::Incident => gitlab_timereport::IssueType::Incident,
+                    IssueType::TestCase => gitlab_timereport::IssueType::TestCase,
+                    IssueType::Requirement => gitlab_timereport::IssueType::Requirement,
+                    IssueType::Task => gitlab_timereport::IssueType::Task,
+                })
+                .collect(),
+            merge_request_issues: args.merge_request_issues,
+            issue_links: args.issue_links,
+            jobs: args.jobs,
+            retry: RetryOptions {
+                max_retries: args.max_retries,
+                initial_delay: Duration::from_secs(args.retry_delay),
+                max_delay: Duration::from_secs(args.max_retry_delay),
+            },
+        },
+        args.sync,
         out_file.clone(),
     )?;
 
@@ -45,3 +95,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+fn migrate(file: String) -> Result<(), Box<dyn std::error::Error>> {
+    let (from, to) = gitlab_timereport::migrate(file.clone())?;
+    if from == to {
+        println!("Database {} already has schema version {}", file, to);
+    } else {
+        println!(
+            "Migrated database {} from schema version {} to {}",
+            file, from, to
+        );
+    }
+
+    Ok(())
+}